@@ -1,27 +1,242 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub enum Profile {
     Monk, // Strict power saving
-    Eco,  // Balanced
+    #[default]
+    Eco, // Balanced
     Pro,  // Performance
 }
 
-#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
 pub struct PowerState {
     pub battery_watts: f32,
     pub cpu_watts: f32,
     pub battery_percent: u8,
-    pub cpu_load: f32,
+    pub cpu_load: f32, // 1-minute load average
+    #[serde(default)]
+    pub cpu_util_percent: f32, // Busy time across all cores since the last tick
+    #[serde(default)]
+    pub per_core_util: Vec<f32>,
+    #[serde(default)]
+    pub temperatures: Vec<Temperature>,
+    #[serde(default)]
+    pub batteries: Vec<BatteryInfo>,
+    #[serde(default)]
+    pub time_to_empty: Option<u64>, // Seconds, on battery
+    #[serde(default)]
+    pub time_to_full: Option<u64>, // Seconds, on AC
+    #[serde(default)]
+    pub dgpu_state: Option<GpuState>, // None if the machine has no discrete GPU
+    #[serde(default)]
+    pub dgpu_blockers: Vec<u32>, // PIDs using /dev/nvidia* that hold up a mode switch
+    #[serde(default)]
+    pub wifi: RadioState,
+    #[serde(default)]
+    pub bluetooth: RadioState,
+    #[serde(default)]
+    pub charge_limit: Option<u8>, // Active charge stop threshold in percent
+    #[serde(default)]
+    pub cpufreq: Option<CpuFreqState>, // None without cpufreq policies
+    #[serde(default)]
+    pub amd_pstate: Option<String>, // amd_pstate driver mode
+    #[serde(default)]
+    pub platform_profile: Option<String>, // ACPI platform profile
+    #[serde(default)]
+    pub paused_containers: Vec<String>, // Names of containers Nitro paused
+    #[serde(default)]
+    pub limits_in_sync: Option<bool>, // Power limits read back equal the target; None if unreadable
+    #[serde(default)]
+    pub last_revert_at: Option<u64>, // Unix seconds the firmware last reverted the limits
+    #[serde(default)]
+    pub revert_count: u32, // Firmware reverts since the daemon started
+    #[serde(default)]
+    pub hardware_error: Option<String>, // Why applying limits, refresh rate or dGPU mode last failed
+    #[serde(default)]
+    pub daemon_stats: DaemonStats,
+    #[serde(default)]
+    pub dry_run: bool, // Actuators only record what they would do
     pub profile: Profile,
     pub wifi_on: bool,
     pub bluetooth_on: bool,
     pub is_plugged_in: bool,
 }
 
+/// Effective cpufreq settings, read back from the first policy.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CpuFreqState {
+    pub governor: Option<String>,
+    pub energy_performance_preference: Option<String>,
+    pub boost: Option<bool>,
+    pub scaling_max_freq: Option<u32>, // kHz
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Temperature {
+    pub label: String, // e.g. "Tctl", "Tccd1", "acpitz", "nvme Composite"
+    pub celsius: f32,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum BatteryStatus {
+    Charging,
+    Discharging,
+    Full,
+    NotCharging,
+    #[default]
+    Unknown,
+}
+
+impl BatteryStatus {
+    /// Parses the kernel's `status` attribute ("Not charging", ...).
+    pub fn from_sysfs(value: &str) -> Self {
+        match value {
+            "Charging" => BatteryStatus::Charging,
+            "Discharging" => BatteryStatus::Discharging,
+            "Full" => BatteryStatus::Full,
+            "Not charging" => BatteryStatus::NotCharging,
+            _ => BatteryStatus::Unknown,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BatteryInfo {
+    pub name: String, // power_supply device, e.g. "BAT1"
+    pub status: BatteryStatus,
+    pub energy_now_wh: Option<f32>,
+    pub energy_full_wh: Option<f32>,
+    pub energy_full_design_wh: Option<f32>,
+    pub cycle_count: Option<u32>,
+    pub technology: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model_name: Option<String>,
+}
+
+impl BatteryInfo {
+    /// Capacity lost relative to the design capacity, in percent.
+    pub fn wear_percent(&self) -> Option<f32> {
+        let full = self.energy_full_wh?;
+        let design = self.energy_full_design_wh?;
+        if design <= 0.0 {
+            return None;
+        }
+        Some(((1.0 - full / design) * 100.0).max(0.0))
+    }
+}
+
+/// rfkill state of a radio class (all WiFi or all Bluetooth devices).
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum RadioState {
+    On,
+    SoftBlocked, // Turned off in software; can be toggled back on
+    HardBlocked, // Physical kill switch or firmware; software can't unblock
+    #[default]
+    Absent,
+}
+
+impl RadioState {
+    pub fn is_on(&self) -> bool {
+        *self == RadioState::On
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GpuState {
+    pub pci_address: String, // e.g. "0000:01:00.0"
+    pub vendor: String,
+    pub power_state: String, // PCI power state: "D0", "D3hot", "D3cold", ...
+    pub runtime_status: String, // Runtime PM: "active", "suspended", ...
+    pub runtime_active_ms: Option<u64>,
+}
+
+impl GpuState {
+    /// True when the GPU is powered down, as CONTEXT.md's truth table demands on battery.
+    /// A runtime-suspended GPU can still sit in D3hot and draw power, so the
+    /// runtime status only counts when the kernel doesn't expose `power_state`.
+    pub fn is_off(&self) -> bool {
+        match self.power_state.as_str() {
+            "unknown" => self.runtime_status == "suspended",
+            state => state == "D3cold",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProcessPower {
+    pub pid: u32,
+    pub name: String,
+    pub cpu_share: f32, // Percent of all CPU time used since the last tick
+    pub watts: f32,     // Estimated share of cpu_watts
+    pub wakeups_per_sec: Option<f32>,
+}
+
+/// A limit/value pair from the `ryzenadj -i` table.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct LimitValue {
+    pub limit: Option<f32>,
+    pub value: Option<f32>,
+}
+
+/// Parsed `ryzenadj -i` output. Power in W, current in A, temperature in °C.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RyzenInfo {
+    pub cpu_family: Option<String>,
+    pub pm_table_version: Option<String>,
+    pub stapm: LimitValue,
+    pub ppt_fast: LimitValue,
+    pub ppt_slow: LimitValue,
+    pub ppt_apu: LimitValue,
+    pub tdc_vdd: LimitValue,
+    pub tdc_soc: LimitValue,
+    pub edc_vdd: LimitValue,
+    pub edc_soc: LimitValue,
+    pub thm_core: LimitValue, // tctl
+    pub stt_apu: LimitValue,
+    pub stt_dgpu: LimitValue,
+    pub stapm_time_const: Option<f32>,
+    pub slow_ppt_time_const: Option<f32>,
+    pub cclk_setpoint: Option<f32>,
+    pub cclk_busy: Option<f32>,
+    /// Rows this version of Nitro doesn't know about, by their ryzenadj name.
+    pub other: BTreeMap<String, f32>,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DaemonCommand {
     SetProfile(Profile),
     ToggleWifi,
     ToggleBluetooth,
+    GetTopConsumers(usize), // Reply: DaemonResponse::TopConsumers with at most N entries
+    GetRyzenInfo,           // Reply: DaemonResponse::RyzenInfo
+    SetChargeLimit(Option<u8>), // None falls back to the profile's charge_limit
+    ResumeContainers,       // Unpause containers Nitro paused; no re-pausing until next on AC
+    GetPlannedActions,      // Reply: DaemonResponse::PlannedActions
+}
+
+/// What the daemon itself costs, to see the effect of skipping reapplies.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DaemonStats {
+    pub limit_applies: u64,     // Power limit writes since start
+    pub skipped_applies: u64,   // Ticks where the limits were in place already
+    pub processes_spawned: u64, // ryzenadj, supergfxctl, compositor commands...
+    pub wakeups_per_sec: f32,   // Context switches of the daemon's threads
+}
+
+/// Something a dry-run daemon would have done to the hardware.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlannedAction {
+    pub action: String, // e.g. "run ryzenadj --stapm-limit=5000"
+    pub count: u32,     // Times it was planned; repeats every tick are folded
+    pub last_at: u64,   // Unix seconds
+}
+
+/// Replies to query commands. Sent on the same socket as the `PowerState`
+/// stream, one JSON object per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum DaemonResponse {
+    TopConsumers(Vec<ProcessPower>),
+    RyzenInfo(Option<Box<RyzenInfo>>), // None if ryzenadj is missing or failed
+    PlannedActions(Vec<PlannedAction>), // Empty unless the daemon runs with --dry-run
 }
@@ -0,0 +1,274 @@
+mod rapl;
+mod ryzenadj;
+
+use crate::attr::read_string;
+use crate::config::{NitroConfig, ProfileConfig};
+use crate::plan;
+use anyhow::Result;
+use nitro_core::{Profile, RyzenInfo};
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::time;
+
+pub use rapl::IntelRapl;
+pub use ryzenadj::RyzenAdj;
+
+/// Boxed so `LimitBackend` stays usable as a trait object.
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Something that can enforce a profile's package power limits.
+pub trait LimitBackend: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Writes the profile's limits (in mW) to the hardware.
+    fn apply_limits<'a>(&'a self, limits: &'a ProfileConfig) -> BoxFuture<'a, Result<()>>;
+
+    /// Reads the limits back and compares them to the profile's.
+    /// None when they can't be read back.
+    fn verify<'a>(&'a self, limits: &'a ProfileConfig) -> BoxFuture<'a, Option<bool>>;
+
+    /// Like `verify`, but without forking anything: from `readings` (the SMU
+    /// table or `ryzenadj -i` the governor read this tick) or cheap sysfs
+    /// reads. None when that isn't enough to tell.
+    fn verify_cached(&self, limits: &ProfileConfig, readings: Option<&RyzenInfo>) -> Option<bool>;
+}
+
+/// Picks the backend from `vendor_id` in `/proc/cpuinfo`. None on CPUs
+/// we have no way to limit.
+pub fn detect_backend(root: &Path) -> Option<Box<dyn LimitBackend>> {
+    let cpuinfo = read_string(&root.join("proc/cpuinfo"))?;
+    let vendor = cpuinfo
+        .lines()
+        .find(|line| line.starts_with("vendor_id"))?
+        .split(':')
+        .nth(1)?
+        .trim();
+    match vendor {
+        "AuthenticAMD" => Some(Box::new(RyzenAdj::new(root))),
+        "GenuineIntel" => Some(Box::new(IntelRapl::new(root))),
+        other => {
+            log::warn!(
+                "Unsupported CPU vendor {}, not applying power limits",
+                other
+            );
+            None
+        }
+    }
+}
+
+/// The profile whose hardware settings apply right now.
+pub fn target_profile(profile: &Profile, is_plugged_in: bool) -> Profile {
+    // If plugged in, IGNORE the dashboard profile and FORCE "Pro" limits.
+    // This ensures that plugging in always uncaps the performance,
+    // even if the dashboard was left on "Monk".
+    if is_plugged_in {
+        Profile::Pro
+    } else {
+        profile.clone()
+    }
+}
+
+pub struct Actuator {
+    last_plugged_in: Option<bool>,
+    force_double_tap: bool,
+    backend: Option<Box<dyn LimitBackend>>,
+    config: NitroConfig,
+    // Read-back telemetry: what the limits looked like after our last apply,
+    // and how often the firmware has changed them since
+    last_target: Option<Profile>,
+    limits_in_sync: Option<bool>,
+    revert_count: u32,
+    last_revert_at: Option<u64>,
+    // Drift-triggered reapplication
+    last_apply: Option<Instant>,
+    paranoid_interval: Duration,
+    applies: u64,
+    skipped_applies: u64,
+}
+
+impl Actuator {
+    pub fn new(config: NitroConfig) -> Self {
+        let backend = detect_backend(&config.sysfs_root);
+        let paranoid_interval = Duration::from_secs(config.paranoid_interval_secs);
+        if let Some(backend) = &backend {
+            log::info!("Using {} for power limits", backend.name());
+        }
+        Self {
+            last_plugged_in: None,
+            force_double_tap: false,
+            backend,
+            config,
+            last_target: None,
+            limits_in_sync: None,
+            revert_count: 0,
+            last_revert_at: None,
+            last_apply: None,
+            paranoid_interval,
+            applies: 0,
+            skipped_applies: 0,
+        }
+    }
+
+    /// The firmware restores its default limits on resume. Make the next
+    /// apply double-tap like an unplug does.
+    pub fn after_resume(&mut self) {
+        self.force_double_tap = true;
+    }
+
+    /// Brings the power limits in line with the profile. Returns the
+    /// backend's error, if any, for the governor to report.
+    /// `readings` are this tick's SMU values, if the governor has them.
+    pub async fn apply_profile(
+        &mut self,
+        profile: &Profile,
+        is_plugged_in: bool,
+        readings: Option<&RyzenInfo>,
+    ) -> Result<()> {
+        let target_profile = &target_profile(profile, is_plugged_in);
+        let just_unplugged = !is_plugged_in && self.last_plugged_in == Some(true);
+
+        // Only reapply when something calls for it. Forking ryzenadj every
+        // tick costs power on the machine we are trying to save power on.
+        let reason = if self.last_target.as_ref() != Some(target_profile) {
+            Some("profile changed")
+        } else if self.last_plugged_in != Some(is_plugged_in) {
+            Some("plug change")
+        } else if self.force_double_tap {
+            Some("resume")
+        } else if self.drifted(target_profile, readings) {
+            Some("drift")
+        } else if self
+            .last_apply
+            .is_none_or(|at| at.elapsed() >= self.paranoid_interval)
+        {
+            // Safety net for firmware changes the cheap check can't see. The
+            // reapply forks anyway, so a fresh read-back first costs little
+            // and catches reverts `drifted` had no readings for.
+            if self.verify_limits(target_profile).await == Some(false) {
+                self.record_mismatch();
+            }
+            Some("paranoid interval")
+        } else {
+            None
+        };
+        self.last_plugged_in = Some(is_plugged_in);
+        let Some(reason) = reason else {
+            self.skipped_applies += 1;
+            return Ok(());
+        };
+
+        // Log the action
+        log::info!("Enforcing limits for {:?} ({})", target_profile, reason);
+
+        // 1. Apply the limits
+        // This is what fights the BIOS watchdog.
+        let mut result = self.apply_limits(target_profile).await;
+
+        // 2. Double-Tap on Unplug (and after resume):
+        // If we just unplugged (AC -> Battery), wait a tiny bit and apply AGAIN.
+        // This ensures the transition sticks if the hardware was busy switching states.
+        if result.is_ok() && (just_unplugged || self.force_double_tap) {
+            time::sleep(Duration::from_millis(100)).await;
+            result = self.apply_limits(target_profile).await;
+        }
+
+        // 3. Check that they stuck
+        self.limits_in_sync = self.verify_limits(target_profile).await;
+        if self.limits_in_sync == Some(false) {
+            log::warn!(
+                "Power limits did not stick after applying {:?}",
+                target_profile
+            );
+        }
+
+        // Update state tracking
+        self.last_target = Some(target_profile.clone());
+        self.last_apply = Some(Instant::now());
+        self.force_double_tap = false;
+        result
+    }
+
+    /// Checks the limits against this tick's readings. A mismatch after they
+    /// were in sync means the firmware reverted them, which is counted.
+    /// Limits that can't be checked without a fork are left to the paranoid
+    /// interval rather than forking `ryzenadj -i` every tick.
+    fn drifted(&mut self, target_profile: &Profile, readings: Option<&RyzenInfo>) -> bool {
+        // Nothing was written in a dry run, so a mismatch means nothing
+        if plan::is_dry_run() {
+            return false;
+        }
+        let Some(backend) = &self.backend else {
+            return false;
+        };
+        match backend.verify_cached(self.config.profile(target_profile), readings) {
+            Some(true) => false,
+            Some(false) => {
+                self.record_mismatch();
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// The limits no longer match; counted as a revert if they did before.
+    fn record_mismatch(&mut self) {
+        if self.limits_in_sync == Some(true) {
+            self.revert_count += 1;
+            self.last_revert_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .ok()
+                .map(|d| d.as_secs());
+            log::warn!(
+                "Firmware reverted the power limits ({} times so far)",
+                self.revert_count
+            );
+        }
+        self.limits_in_sync = Some(false);
+    }
+
+    /// Times the limits were (re)applied, and ticks where that was skipped.
+    pub fn apply_counts(&self) -> (u64, u64) {
+        (self.applies, self.skipped_applies)
+    }
+
+    /// Whether the hardware holds the limits we applied. None if unknown.
+    pub fn limits_in_sync(&self) -> Option<bool> {
+        self.limits_in_sync
+    }
+
+    pub fn revert_count(&self) -> u32 {
+        self.revert_count
+    }
+
+    /// When the limits were last applied.
+    pub fn last_apply(&self) -> Option<Instant> {
+        self.last_apply
+    }
+
+    /// Unix time of the last revert we noticed.
+    pub fn last_revert_at(&self) -> Option<u64> {
+        self.last_revert_at
+    }
+
+    async fn verify_limits(&self, profile: &Profile) -> Option<bool> {
+        // Nothing was written in a dry run, so a mismatch means nothing
+        if plan::is_dry_run() {
+            return None;
+        }
+        self.backend
+            .as_ref()?
+            .verify(self.config.profile(profile))
+            .await
+    }
+
+    async fn apply_limits(&mut self, profile: &Profile) -> Result<()> {
+        let Some(backend) = &self.backend else {
+            return Ok(());
+        };
+        let result = backend.apply_limits(self.config.profile(profile)).await;
+        self.applies += 1;
+        result
+    }
+}
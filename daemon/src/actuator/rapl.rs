@@ -0,0 +1,252 @@
+use super::{BoxFuture, LimitBackend};
+use crate::attr::{self, read_string, write_if_changed};
+use crate::config::ProfileConfig;
+use anyhow::{bail, Result};
+use nitro_core::RyzenInfo;
+use std::fs;
+use std::path::PathBuf;
+
+/// RAPL stores limits in hardware power units (often 1/8 W), so the value
+/// read back is the written one rounded to that step.
+const TOLERANCE_UW: u64 = 500_000;
+
+/// Intel: PL1/PL2 through the powercap RAPL package zones.
+///
+/// AMD's sustained limits (STAPM, slow PPT) both become PL1, taking the lower
+/// one; the fast PPT limit becomes PL2.
+pub struct IntelRapl {
+    root: PathBuf,
+}
+
+impl IntelRapl {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Package zones (`intel-rapl:0`, not subzones like `intel-rapl:0:0`).
+    /// The MMIO zones are included: the hardware enforces the lower of the
+    /// MSR and MMIO limits, so both have to be written.
+    fn package_zones(&self) -> Vec<PathBuf> {
+        let mut zones: Vec<PathBuf> = fs::read_dir(self.root.join("sys/class/powercap"))
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|zone| {
+                let name = zone.file_name().map(|n| n.to_string_lossy().into_owned());
+                name.is_some_and(|n| {
+                    (n.starts_with("intel-rapl:") || n.starts_with("intel-rapl-mmio:"))
+                        && n.matches(':').count() == 1
+                })
+            })
+            .collect();
+        zones.sort();
+        zones
+    }
+}
+
+impl LimitBackend for IntelRapl {
+    fn name(&self) -> &'static str {
+        "intel-rapl"
+    }
+
+    // Plain sysfs writes and reads: quick enough to run inline
+    fn apply_limits<'a>(&'a self, limits: &'a ProfileConfig) -> BoxFuture<'a, Result<()>> {
+        Box::pin(async move { self.write_limits(limits) })
+    }
+
+    fn verify<'a>(&'a self, limits: &'a ProfileConfig) -> BoxFuture<'a, Option<bool>> {
+        Box::pin(async move { self.read_back(limits) })
+    }
+
+    fn verify_cached(&self, limits: &ProfileConfig, _: Option<&RyzenInfo>) -> Option<bool> {
+        self.read_back(limits)
+    }
+}
+
+impl IntelRapl {
+    fn write_limits(&self, limits: &ProfileConfig) -> Result<()> {
+        let zones = self.package_zones();
+        if zones.is_empty() {
+            bail!("No intel-rapl package zones under /sys/class/powercap");
+        }
+
+        let (pl1_uw, pl2_uw) = pl_limits_uw(limits);
+        log::info!(
+            "Applying RAPL Limits: PL1={}mW PL2={}mW",
+            pl1_uw / 1000,
+            pl2_uw / 1000
+        );
+
+        for zone in zones {
+            for n in 0..4 {
+                let Some(name) = read_string(&zone.join(format!("constraint_{}_name", n))) else {
+                    continue;
+                };
+                let (power_uw, window_us) = match name.as_str() {
+                    "long_term" => (pl1_uw, limits.pl1_time_window_us),
+                    "short_term" => (pl2_uw, limits.pl2_time_window_us),
+                    // peak_power (PL4) is left to the firmware
+                    _ => continue,
+                };
+                write_if_changed(
+                    &zone.join(format!("constraint_{}_power_limit_uw", n)),
+                    &power_uw.to_string(),
+                )?;
+                if let Some(window_us) = window_us {
+                    write_if_changed(
+                        &zone.join(format!("constraint_{}_time_window_us", n)),
+                        &window_us.to_string(),
+                    )?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_back(&self, limits: &ProfileConfig) -> Option<bool> {
+        let (pl1_uw, pl2_uw) = pl_limits_uw(limits);
+        let mut checked = false;
+        for zone in self.package_zones() {
+            for n in 0..4 {
+                let Some(name) = read_string(&zone.join(format!("constraint_{}_name", n))) else {
+                    continue;
+                };
+                let target = match name.as_str() {
+                    "long_term" => pl1_uw,
+                    "short_term" => pl2_uw,
+                    _ => continue,
+                };
+                let current: u64 =
+                    attr::read(&zone.join(format!("constraint_{}_power_limit_uw", n)))?;
+                if current.abs_diff(target) > TOLERANCE_UW {
+                    return Some(false);
+                }
+                checked = true;
+            }
+        }
+        checked.then_some(true)
+    }
+}
+
+/// PL1 and PL2 in µW for a profile's limits.
+fn pl_limits_uw(limits: &ProfileConfig) -> (u64, u64) {
+    (
+        u64::from(limits.stapm_limit.min(limits.slow_limit)) * 1000,
+        u64::from(limits.fast_limit) * 1000,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_sysfs::FakeSysfs;
+
+    fn limits() -> ProfileConfig {
+        ProfileConfig {
+            stapm_limit: 25000,
+            fast_limit: 35000,
+            slow_limit: 20000,
+            tctl_temp: None,
+            charge_limit: None,
+            refresh_rate: None,
+            gpu_mode: None,
+            governor: None,
+            energy_performance_preference: None,
+            boost: None,
+            scaling_max_freq: None,
+            amd_pstate: None,
+            platform_profile: None,
+            pl1_time_window_us: None,
+            pl2_time_window_us: None,
+        }
+    }
+
+    /// A package zone with PL1, PL2 and PL4 constraints, all at 1 W.
+    fn zone(sysfs: &FakeSysfs, zone: &str) {
+        for (n, name) in ["long_term", "short_term", "peak_power"].iter().enumerate() {
+            sysfs
+                .write(
+                    &format!("sys/class/powercap/{}/constraint_{}_name", zone, n),
+                    name,
+                )
+                .write(
+                    &format!(
+                        "sys/class/powercap/{}/constraint_{}_power_limit_uw",
+                        zone, n
+                    ),
+                    "1000000",
+                );
+        }
+    }
+
+    fn limit_uw(sysfs: &FakeSysfs, zone: &str, n: u32) -> Option<String> {
+        sysfs.read(&format!(
+            "sys/class/powercap/{}/constraint_{}_power_limit_uw",
+            zone, n
+        ))
+    }
+
+    #[test]
+    fn pl1_is_the_lower_sustained_limit() {
+        assert_eq!(pl_limits_uw(&limits()), (20_000_000, 35_000_000));
+        let stapm_lower = ProfileConfig {
+            stapm_limit: 15000,
+            ..limits()
+        };
+        assert_eq!(pl_limits_uw(&stapm_lower), (15_000_000, 35_000_000));
+    }
+
+    #[test]
+    fn only_package_pl1_and_pl2_are_written() {
+        let sysfs = FakeSysfs::new("rapl-zones");
+        for name in [
+            "intel-rapl:0",
+            "intel-rapl:1",
+            "intel-rapl-mmio:0",
+            "intel-rapl:0:0",
+        ] {
+            zone(&sysfs, name);
+        }
+        let rapl = IntelRapl::new(sysfs.root());
+        assert_eq!(rapl.read_back(&limits()), Some(false));
+
+        rapl.write_limits(&limits()).unwrap();
+
+        for name in ["intel-rapl:0", "intel-rapl:1", "intel-rapl-mmio:0"] {
+            assert_eq!(
+                limit_uw(&sysfs, name, 0).as_deref(),
+                Some("20000000"),
+                "{}",
+                name
+            );
+            assert_eq!(
+                limit_uw(&sysfs, name, 1).as_deref(),
+                Some("35000000"),
+                "{}",
+                name
+            );
+            assert_eq!(
+                limit_uw(&sysfs, name, 2).as_deref(),
+                Some("1000000"),
+                "{}",
+                name
+            );
+        }
+        for n in 0..3 {
+            assert_eq!(
+                limit_uw(&sysfs, "intel-rapl:0:0", n).as_deref(),
+                Some("1000000")
+            );
+        }
+        assert_eq!(rapl.read_back(&limits()), Some(true));
+    }
+
+    #[test]
+    fn missing_zones_are_an_error() {
+        let sysfs = FakeSysfs::new("rapl-none");
+        let rapl = IntelRapl::new(sysfs.root());
+        assert!(rapl.write_limits(&limits()).is_err());
+        assert_eq!(rapl.read_back(&limits()), None);
+    }
+}
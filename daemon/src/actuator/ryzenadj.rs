@@ -0,0 +1,95 @@
+use super::{BoxFuture, LimitBackend};
+use crate::config::ProfileConfig;
+use crate::exec;
+use crate::plan;
+use crate::ryzen_info;
+use crate::sensors::{SensorBackend, SysfsBackend};
+use anyhow::{bail, Result};
+use nitro_core::{LimitValue, RyzenInfo};
+use std::path::PathBuf;
+use tokio::process::Command;
+
+/// The SMU stores limits as floats; anything closer than this counts as equal.
+const TOLERANCE_W: f32 = 0.5;
+
+/// AMD: STAPM and PPT limits through the `ryzenadj` CLI.
+pub struct RyzenAdj {
+    // Reads the pm_table back for verification
+    sensors: SysfsBackend,
+}
+
+impl RyzenAdj {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            sensors: SysfsBackend::new(root),
+        }
+    }
+
+    async fn apply(&self, limits: &ProfileConfig) -> Result<()> {
+        let mut args = vec![
+            format!("--slow-limit={}", limits.slow_limit),
+            format!("--fast-limit={}", limits.fast_limit),
+            format!("--stapm-limit={}", limits.stapm_limit),
+        ];
+        if let Some(temp) = limits.tctl_temp {
+            args.push(format!("--tctl-temp={}", temp));
+        }
+
+        // Log what we are doing
+        log::info!("Applying Ryzen Limits: {:?}", args);
+
+        let mut cmd = Command::new("ryzenadj");
+        cmd.args(&args);
+        if !plan::allow_command(&cmd) {
+            return Ok(());
+        }
+        let output = exec::output(&mut cmd, exec::TIMEOUT).await?;
+        if !output.status.success() {
+            bail!(
+                "ryzenadj failed: {}",
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Ok(())
+    }
+
+    async fn read_back(&self, limits: &ProfileConfig) -> Option<bool> {
+        // The pm_table is a file read; only fork `ryzenadj -i` without it
+        let info = match self.sensors.pm_table().and_then(|table| table.decode()) {
+            Some(info) => info,
+            None => ryzen_info::read().await?,
+        };
+        compare(&info, limits)
+    }
+}
+
+fn compare(info: &RyzenInfo, limits: &ProfileConfig) -> Option<bool> {
+    let matches = |read: &LimitValue, target_mw: u32| {
+        read.limit
+            .map(|w| (w - target_mw as f32 / 1000.0).abs() < TOLERANCE_W)
+    };
+    let checks = [
+        matches(&info.stapm, limits.stapm_limit)?,
+        matches(&info.ppt_fast, limits.fast_limit)?,
+        matches(&info.ppt_slow, limits.slow_limit)?,
+    ];
+    Some(checks.iter().all(|&ok| ok))
+}
+
+impl LimitBackend for RyzenAdj {
+    fn name(&self) -> &'static str {
+        "ryzenadj"
+    }
+
+    fn apply_limits<'a>(&'a self, limits: &'a ProfileConfig) -> BoxFuture<'a, Result<()>> {
+        Box::pin(self.apply(limits))
+    }
+
+    fn verify<'a>(&'a self, limits: &'a ProfileConfig) -> BoxFuture<'a, Option<bool>> {
+        Box::pin(self.read_back(limits))
+    }
+
+    fn verify_cached(&self, limits: &ProfileConfig, readings: Option<&RyzenInfo>) -> Option<bool> {
+        compare(readings?, limits)
+    }
+}
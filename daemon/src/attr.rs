@@ -0,0 +1,33 @@
+use crate::plan;
+use anyhow::{Context, Result};
+use std::fs;
+use std::path::Path;
+use std::str::FromStr;
+
+/// Writes a sysfs attribute, or only records the write in dry-run mode.
+pub fn write(path: &Path, value: &str) -> Result<()> {
+    if !plan::allow(format!("write {:?} to {}", value, path.display())) {
+        return Ok(());
+    }
+    fs::write(path, value).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Writes a sysfs attribute unless it already holds `value`.
+pub fn write_if_changed(path: &Path, value: &str) -> Result<()> {
+    if read_string(path).as_deref() == Some(value) {
+        return Ok(());
+    }
+    if !plan::is_dry_run() {
+        log::info!("Setting {} to {}", path.display(), value);
+    }
+    write(path, value)
+}
+
+pub fn read_string(path: &Path) -> Option<String> {
+    fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+/// Reads and parses a single-value attribute such as `online` or `capacity`.
+pub fn read<T: FromStr>(path: &Path) -> Option<T> {
+    read_string(path)?.parse().ok()
+}
@@ -0,0 +1,195 @@
+use crate::attr;
+use anyhow::{bail, Result};
+use std::fs;
+use std::path::PathBuf;
+
+/// How far below the end threshold charging resumes.
+const START_HYSTERESIS: u8 = 5;
+
+/// The acer-wmi-battery module only knows "health mode", which caps at 80%.
+const ACER_HEALTH_MODE_LIMIT: u8 = 80;
+const ACER_HEALTH_MODE: &str = "sys/bus/wmi/drivers/acer-wmi-battery/health_mode";
+
+/// Writes battery charge thresholds, via the standard
+/// `charge_control_{start,end}_threshold` attributes or Acer's health mode.
+pub struct ChargeLimiter {
+    root: PathBuf,
+}
+
+impl ChargeLimiter {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Battery directories that expose `charge_control_end_threshold`.
+    fn threshold_batteries(&self) -> Vec<PathBuf> {
+        let class_dir = self.root.join("sys/class/power_supply");
+        let mut dirs: Vec<PathBuf> = fs::read_dir(class_dir)
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|dir| dir.join("charge_control_end_threshold").exists())
+            .collect();
+        dirs.sort();
+        dirs
+    }
+
+    /// The limit currently in effect, as read back from the hardware.
+    pub fn current(&self) -> Option<u8> {
+        if let Some(dir) = self.threshold_batteries().first() {
+            return attr::read::<u8>(&dir.join("charge_control_end_threshold"));
+        }
+        match attr::read::<u8>(&self.root.join(ACER_HEALTH_MODE))? {
+            0 => Some(100),
+            _ => Some(ACER_HEALTH_MODE_LIMIT),
+        }
+    }
+
+    pub fn apply(&self, limit: u8) -> Result<()> {
+        if !(1..=100).contains(&limit) {
+            bail!("Charge limit {}% is out of range", limit);
+        }
+
+        let batteries = self.threshold_batteries();
+        if batteries.is_empty() {
+            return self.apply_acer_health_mode(limit);
+        }
+
+        for dir in batteries {
+            log::info!("Setting charge limit {}% on {}", limit, dir.display());
+            let current_end = attr::read::<u8>(&dir.join("charge_control_end_threshold"));
+            let has_start = dir.join("charge_control_start_threshold").exists();
+            for (name, value) in threshold_writes(current_end, limit, has_start) {
+                attr::write(&dir.join(name), &value.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply_acer_health_mode(&self, limit: u8) -> Result<()> {
+        let path = self.root.join(ACER_HEALTH_MODE);
+        if !path.exists() {
+            bail!("No charge threshold control found (no charge_control_end_threshold or acer-wmi-battery)");
+        }
+        let enable = limit < 100;
+        if enable && limit != ACER_HEALTH_MODE_LIMIT {
+            log::warn!(
+                "acer-wmi-battery only supports an {}% limit; using that instead of {}%",
+                ACER_HEALTH_MODE_LIMIT,
+                limit
+            );
+        }
+        log::info!("Setting Acer battery health mode {}", enable);
+        attr::write(&path, if enable { "1" } else { "0" })?;
+        Ok(())
+    }
+}
+
+/// The threshold attributes to write, in order, to move the end threshold
+/// from `current_end` to `limit`. The kernel rejects start > end, so start
+/// goes first when lowering and last when raising.
+fn threshold_writes(
+    current_end: Option<u8>,
+    limit: u8,
+    has_start: bool,
+) -> Vec<(&'static str, u8)> {
+    let start = (
+        "charge_control_start_threshold",
+        limit.saturating_sub(START_HYSTERESIS),
+    );
+    let end = ("charge_control_end_threshold", limit);
+    let lowering = current_end.is_some_and(|current| limit < current);
+    match (has_start, lowering) {
+        (false, _) => vec![end],
+        (true, true) => vec![start, end],
+        (true, false) => vec![end, start],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_sysfs::FakeSysfs;
+
+    const START: &str = "charge_control_start_threshold";
+    const END: &str = "charge_control_end_threshold";
+
+    #[test]
+    fn start_goes_first_when_lowering() {
+        assert_eq!(
+            threshold_writes(Some(100), 80, true),
+            vec![(START, 75), (END, 80)]
+        );
+    }
+
+    #[test]
+    fn end_goes_first_when_raising() {
+        assert_eq!(
+            threshold_writes(Some(80), 100, true),
+            vec![(END, 100), (START, 95)]
+        );
+        assert_eq!(
+            threshold_writes(None, 90, true),
+            vec![(END, 90), (START, 85)]
+        );
+    }
+
+    #[test]
+    fn only_end_without_a_start_threshold() {
+        assert_eq!(threshold_writes(Some(100), 60, false), vec![(END, 60)]);
+    }
+
+    #[test]
+    fn limit_is_set_and_lifted_on_every_battery() {
+        let sysfs = FakeSysfs::new("charge-thresholds");
+        sysfs
+            .supply("BAT0", &[(END, "100"), (START, "95")])
+            .supply("BAT1", &[(END, "100")])
+            .supply("ACAD0", &[("online", "1")]);
+        let charge = ChargeLimiter::new(sysfs.root());
+
+        charge.apply(80).unwrap();
+        assert_eq!(charge.current(), Some(80));
+        assert_eq!(
+            sysfs
+                .read("sys/class/power_supply/BAT0/charge_control_start_threshold")
+                .as_deref(),
+            Some("75")
+        );
+        assert_eq!(
+            sysfs
+                .read("sys/class/power_supply/BAT1/charge_control_end_threshold")
+                .as_deref(),
+            Some("80")
+        );
+        assert_eq!(
+            sysfs.read("sys/class/power_supply/BAT1/charge_control_start_threshold"),
+            None
+        );
+
+        charge.apply(100).unwrap();
+        assert_eq!(charge.current(), Some(100));
+        assert_eq!(
+            sysfs
+                .read("sys/class/power_supply/BAT0/charge_control_start_threshold")
+                .as_deref(),
+            Some("95")
+        );
+    }
+
+    #[test]
+    fn acer_health_mode_is_switched_off_at_100() {
+        let sysfs = FakeSysfs::new("charge-acer");
+        sysfs.write(ACER_HEALTH_MODE, "0");
+        let charge = ChargeLimiter::new(sysfs.root());
+
+        charge.apply(80).unwrap();
+        assert_eq!(sysfs.read(ACER_HEALTH_MODE).as_deref(), Some("1"));
+        assert_eq!(charge.current(), Some(80));
+
+        charge.apply(100).unwrap();
+        assert_eq!(sysfs.read(ACER_HEALTH_MODE).as_deref(), Some("0"));
+        assert_eq!(charge.current(), Some(100));
+    }
+}
@@ -1,6 +1,8 @@
 use anyhow::Result;
 use config::{Config, File};
+use nitro_core::Profile;
 use serde::Deserialize;
+use std::path::PathBuf;
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct ProfileConfig {
@@ -8,6 +10,85 @@ pub struct ProfileConfig {
     pub fast_limit: u32,
     pub slow_limit: u32,
     pub tctl_temp: Option<u32>,
+    /// Stop charging at this percentage (e.g. 80 for a laptop that lives on AC).
+    pub charge_limit: Option<u8>,
+    /// Internal panel refresh rate in Hz. Needs a `[display]` section.
+    pub refresh_rate: Option<u32>,
+    /// Discrete GPU mode to switch to. Left alone when unset.
+    pub gpu_mode: Option<GpuMode>,
+    /// cpufreq scaling governor, e.g. "powersave".
+    pub governor: Option<String>,
+    /// EPP hint for amd-pstate/intel_pstate active mode, e.g. "power".
+    pub energy_performance_preference: Option<String>,
+    /// CPU boost (turbo) on or off.
+    pub boost: Option<bool>,
+    /// Upper frequency bound in kHz.
+    pub scaling_max_freq: Option<u32>,
+    /// amd_pstate driver mode: "active", "passive" or "guided".
+    pub amd_pstate: Option<String>,
+    /// ACPI platform profile, one of `platform_profile_choices`.
+    pub platform_profile: Option<String>,
+    /// Intel only: PL1/PL2 averaging windows in µs. Firmware defaults when unset.
+    pub pl1_time_window_us: Option<u64>,
+    pub pl2_time_window_us: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuMode {
+    Integrated,
+    Hybrid,
+}
+
+impl GpuMode {
+    /// Name as used by supergfxctl.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GpuMode::Integrated => "Integrated",
+            GpuMode::Hybrid => "Hybrid",
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum DisplayBackend {
+    Niri,
+    Sway,
+    Hyprland,
+    WlrRandr,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct DisplayConfig {
+    pub backend: DisplayBackend,
+    pub output: String,
+    /// Resolution part of the mode, e.g. "1920x1080".
+    pub mode: String,
+    /// Only drive this user's session. Defaults to the first logged-in user.
+    pub uid: Option<u32>,
+    /// Hyprland only: monitor position ("0x0") and scale, which a monitor
+    /// rule always sets. Default to the output's current ones.
+    pub position: Option<String>,
+    pub scale: Option<f32>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ContainerConfig {
+    /// Pause idle containers on battery. Paused ones are still unpaused on AC when off.
+    pub enabled: bool,
+    /// Engine API sockets (Docker, rootful Podman).
+    pub sockets: Vec<PathBuf>,
+    /// Total CPU usage below which the machine counts as idle.
+    pub idle_cpu_percent: f32,
+    /// How long it has to stay idle before containers are paused.
+    pub idle_secs: u64,
+    /// Only pause these containers (by name). Empty means all.
+    #[serde(default)]
+    pub allow: Vec<String>,
+    /// Never pause these containers (by name).
+    #[serde(default)]
+    pub deny: Vec<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -15,6 +96,22 @@ pub struct NitroConfig {
     pub monk: ProfileConfig,
     pub eco: ProfileConfig,
     pub pro: ProfileConfig,
+    /// Prefix for every /sys and /proc path the daemon reads.
+    /// "/" on real hardware; point it at a fake tree for testing.
+    pub sysfs_root: PathBuf,
+    /// Also sample per-process wakeups from /proc/[pid]/sched for the
+    /// top consumers list. Needs CONFIG_SCHED_DEBUG and costs extra reads.
+    pub track_wakeups: bool,
+    /// Where the daemon keeps state across reboots (e.g. the charge limit).
+    pub state_file: PathBuf,
+    /// Refresh rate control; disabled when the section is missing.
+    pub display: Option<DisplayConfig>,
+    pub containers: ContainerConfig,
+    /// Log and record actions instead of touching the hardware.
+    pub dry_run: bool,
+    /// Reapply the power limits at least this often even when the read-back
+    /// says they are still in place.
+    pub paranoid_interval_secs: u64,
 }
 
 impl NitroConfig {
@@ -32,9 +129,35 @@ impl NitroConfig {
             .set_default("pro.stapm_limit", 25000)?
             .set_default("pro.fast_limit", 35000)?
             .set_default("pro.slow_limit", 25000)?
+            .set_default("monk.refresh_rate", 60)?
+            .set_default("eco.refresh_rate", 60)?
+            .set_default("pro.refresh_rate", 144)?
+            .set_default("monk.boost", false)?
+            .set_default("eco.boost", false)?
+            .set_default("pro.boost", true)?
+            .set_default("sysfs_root", "/")?
+            .set_default("track_wakeups", false)?
+            .set_default("dry_run", false)?
+            .set_default("paranoid_interval_secs", 60)?
+            .set_default("state_file", "/var/lib/nitro/state.json")?
+            .set_default("containers.enabled", false)?
+            .set_default(
+                "containers.sockets",
+                vec!["/var/run/docker.sock", "/run/podman/podman.sock"],
+            )?
+            .set_default("containers.idle_cpu_percent", 5.0)?
+            .set_default("containers.idle_secs", 120)?
             .add_source(File::with_name(config_path).required(false));
 
         let config = builder.build()?;
         Ok(config.try_deserialize()?)
     }
+
+    pub fn profile(&self, profile: &Profile) -> &ProfileConfig {
+        match profile {
+            Profile::Monk => &self.monk,
+            Profile::Eco => &self.eco,
+            Profile::Pro => &self.pro,
+        }
+    }
 }
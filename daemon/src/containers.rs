@@ -0,0 +1,270 @@
+use crate::config::ContainerConfig;
+use crate::persist::{PersistedState, StateStore};
+use crate::plan;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use tokio::sync::{mpsc, watch};
+use tokio::time;
+
+/// Engine API calls are local, but a wedged daemon must not stall the governor.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A container Nitro paused itself, so only those are ever unpaused.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PausedContainer {
+    pub socket: PathBuf,
+    pub id: String,
+    pub name: String,
+}
+
+/// What the pauser needs from each governor tick.
+#[derive(Debug, Clone, Copy)]
+pub struct ContainerTarget {
+    pub cpu_util_percent: f32,
+    pub is_plugged_in: bool,
+}
+
+/// Runs the pauser on its own task: every Engine API call may take up to
+/// its timeout, which must not hold up the governor's tick.
+///
+/// Like the hardware worker, it only acts on the latest target. A message on
+/// `resume_rx` unpauses everything (`DaemonCommand::ResumeContainers`).
+/// Paused containers are recorded in `persisted` and saved through `store`.
+pub fn spawn(
+    config: ContainerConfig,
+    store: StateStore,
+    persisted: Arc<Mutex<PersistedState>>,
+    mut target_rx: watch::Receiver<Option<ContainerTarget>>,
+    mut resume_rx: mpsc::Receiver<()>,
+) {
+    tokio::spawn(async move {
+        let mut pauser = ContainerPauser::new(config);
+        loop {
+            // Only this task changes the list, so working on a copy is safe
+            let mut paused = persisted.lock().unwrap().paused_containers.clone();
+            let changed = tokio::select! {
+                changed = target_rx.changed() => {
+                    if changed.is_err() {
+                        return;
+                    }
+                    let Some(target) = *target_rx.borrow_and_update() else {
+                        continue;
+                    };
+                    pauser
+                        .update(target.cpu_util_percent, target.is_plugged_in, &mut paused)
+                        .await
+                }
+                Some(()) = resume_rx.recv() => pauser.resume(&mut paused).await,
+            };
+            if changed {
+                let mut persisted = persisted.lock().unwrap();
+                persisted.paused_containers = paused;
+                if let Err(e) = store.save(&persisted) {
+                    log::error!("Failed to save paused containers: {}", e);
+                }
+            }
+        }
+    });
+}
+
+/// Pauses running Docker/Podman containers while the machine sits idle on
+/// battery, and unpauses them on AC or when asked to.
+pub struct ContainerPauser {
+    config: ContainerConfig,
+    idle_since: Option<Instant>,
+    // Set by an on-demand resume: don't pause again until the next time on AC
+    inhibited: bool,
+}
+
+/// The subset of `GET /containers/json` we use.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Container {
+    id: String,
+    #[serde(default)]
+    names: Vec<String>,
+    #[serde(default)]
+    state: String,
+}
+
+impl ContainerPauser {
+    pub fn new(config: ContainerConfig) -> Self {
+        Self {
+            config,
+            idle_since: None,
+            inhibited: false,
+        }
+    }
+
+    /// Called once per tick. Returns true if `paused` changed and needs saving.
+    pub async fn update(
+        &mut self,
+        cpu_util_percent: f32,
+        is_plugged_in: bool,
+        paused: &mut Vec<PausedContainer>,
+    ) -> bool {
+        if is_plugged_in {
+            self.idle_since = None;
+            self.inhibited = false;
+            if paused.is_empty() {
+                return false;
+            }
+            log::info!("On AC, unpausing {} container(s)", paused.len());
+            return unpause_all(paused).await;
+        }
+
+        if !self.config.enabled || self.inhibited {
+            return false;
+        }
+        if cpu_util_percent >= self.config.idle_cpu_percent {
+            self.idle_since = None;
+            return false;
+        }
+        let idle_since = *self.idle_since.get_or_insert_with(Instant::now);
+        if idle_since.elapsed() < Duration::from_secs(self.config.idle_secs) {
+            return false;
+        }
+
+        // Start a new idle period, so containers started later get paused too
+        self.idle_since = None;
+        let before = paused.len();
+        for socket in &self.config.sockets {
+            if !socket.exists() {
+                continue;
+            }
+            if let Err(e) = self.pause_running(socket, paused).await {
+                log::error!("Failed to pause containers on {}: {}", socket.display(), e);
+            }
+        }
+        paused.len() != before
+    }
+
+    /// Unpauses everything Nitro paused and holds off until the next time on AC.
+    pub async fn resume(&mut self, paused: &mut Vec<PausedContainer>) -> bool {
+        self.inhibited = true;
+        self.idle_since = None;
+        unpause_all(paused).await
+    }
+
+    async fn pause_running(&self, socket: &Path, paused: &mut Vec<PausedContainer>) -> Result<()> {
+        let body = request(socket, "GET", "/containers/json").await?;
+        let containers: Vec<Container> =
+            serde_json::from_str(&body).context("Unexpected /containers/json reply")?;
+
+        for container in containers {
+            if container.state != "running" {
+                continue;
+            }
+            let name = container
+                .names
+                .first()
+                .map(|n| n.trim_start_matches('/').to_string())
+                .unwrap_or_else(|| container.id.chars().take(12).collect());
+            if !self.is_allowed(&name) {
+                continue;
+            }
+
+            let path = format!("/containers/{}/pause", container.id);
+            if !plan::allow(format!("POST {} on {}", path, socket.display())) {
+                continue;
+            }
+            log::info!("Idle on battery, pausing container {}", name);
+            match request(socket, "POST", &path).await {
+                Ok(_) => paused.push(PausedContainer {
+                    socket: socket.to_path_buf(),
+                    id: container.id,
+                    name,
+                }),
+                Err(e) => log::error!("Failed to pause {}: {}", name, e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Denylist wins; a non-empty allowlist limits pausing to its entries.
+    fn is_allowed(&self, name: &str) -> bool {
+        if self.config.deny.iter().any(|d| d == name) {
+            return false;
+        }
+        self.config.allow.is_empty() || self.config.allow.iter().any(|a| a == name)
+    }
+}
+
+/// Unpauses recorded containers. Entries stay recorded only if the engine
+/// couldn't be reached, so they are retried later.
+async fn unpause_all(paused: &mut Vec<PausedContainer>) -> bool {
+    let before = paused.len();
+    let mut kept = Vec::new();
+    for container in paused.drain(..) {
+        let path = format!("/containers/{}/unpause", container.id);
+        if !plan::allow(format!("POST {} on {}", path, container.socket.display())) {
+            kept.push(container);
+            continue;
+        }
+        match request(&container.socket, "POST", &path).await {
+            Ok(_) => log::info!("Unpaused container {}", container.name),
+            // 404/409: removed or already unpaused by someone else; forget it
+            Err(e) if e.downcast_ref::<HttpStatus>().is_some() => {
+                log::warn!("Not unpausing {}: {}", container.name, e)
+            }
+            Err(e) => {
+                log::error!("Failed to unpause {}: {}", container.name, e);
+                kept.push(container);
+            }
+        }
+    }
+    *paused = kept;
+    paused.len() != before
+}
+
+/// A non-2xx reply from the engine.
+#[derive(Debug)]
+struct HttpStatus(u16, String);
+
+impl std::fmt::Display for HttpStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HTTP {}: {}", self.0, self.1.trim())
+    }
+}
+
+impl std::error::Error for HttpStatus {}
+
+/// One HTTP/1.0 request to the Engine API (Docker or Podman's compat API).
+/// HTTP/1.0 makes the engine close the connection after the body, so there
+/// is no chunked encoding or keep-alive to deal with.
+async fn request(socket: &Path, method: &str, path: &str) -> Result<String> {
+    let exchange = async {
+        let mut stream = UnixStream::connect(socket).await?;
+        let req = format!(
+            "{} {} HTTP/1.0\r\nHost: localhost\r\nContent-Length: 0\r\n\r\n",
+            method, path
+        );
+        stream.write_all(req.as_bytes()).await?;
+        let mut reply = Vec::new();
+        stream.read_to_end(&mut reply).await?;
+        anyhow::Ok(reply)
+    };
+    let reply = time::timeout(REQUEST_TIMEOUT, exchange)
+        .await
+        .with_context(|| format!("{} timed out", socket.display()))?
+        .with_context(|| format!("Failed to talk to {}", socket.display()))?;
+
+    let reply = String::from_utf8_lossy(&reply);
+    let Some((head, body)) = reply.split_once("\r\n\r\n") else {
+        bail!("Malformed reply from {}", socket.display());
+    };
+    let status: u16 = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .context("Malformed status line")?;
+    if !(200..300).contains(&status) {
+        return Err(HttpStatus(status, body.to_string()).into());
+    }
+    Ok(body.to_string())
+}
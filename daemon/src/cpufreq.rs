@@ -0,0 +1,151 @@
+use crate::attr::{read, read_string, write_if_changed};
+use crate::config::ProfileConfig;
+use crate::log_once::LogOnce;
+use anyhow::{bail, Result};
+use nitro_core::CpuFreqState;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Applies a profile's cpufreq settings to every `policy*` directory.
+///
+/// Values are read back and only written when they differ, so this is cheap
+/// enough to run every tick and undo whatever else changed them.
+pub struct CpuFreq {
+    root: PathBuf,
+    errors: LogOnce,
+}
+
+impl CpuFreq {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            errors: LogOnce::default(),
+        }
+    }
+
+    fn cpufreq_dir(&self) -> PathBuf {
+        self.root.join("sys/devices/system/cpu/cpufreq")
+    }
+
+    fn policies(&self) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = fs::read_dir(self.cpufreq_dir())
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|e| e.path())
+            .filter(|dir| {
+                dir.file_name()
+                    .is_some_and(|n| n.to_string_lossy().starts_with("policy"))
+            })
+            .collect();
+        dirs.sort();
+        dirs
+    }
+
+    pub fn apply(&mut self, profile: &ProfileConfig) {
+        let result = self.try_apply(profile);
+        self.errors.report("apply cpufreq settings", result);
+    }
+
+    fn try_apply(&self, profile: &ProfileConfig) -> Result<()> {
+        if let Some(boost) = profile.boost {
+            let value = if boost { "1" } else { "0" };
+            // Global switch (acpi-cpufreq), otherwise amd-pstate's per-policy one
+            let global = self.cpufreq_dir().join("boost");
+            if global.exists() {
+                write_if_changed(&global, value)?;
+            } else {
+                for policy in self.policies() {
+                    let attr = policy.join("boost");
+                    if attr.exists() {
+                        write_if_changed(&attr, value)?;
+                    }
+                }
+            }
+        }
+
+        for policy in self.policies() {
+            // Governor first: EPP can only be changed under a matching governor
+            if let Some(governor) = &profile.governor {
+                check_available(&policy, "scaling_available_governors", governor)?;
+                write_if_changed(&policy.join("scaling_governor"), governor)?;
+            }
+            if let Some(epp) = &profile.energy_performance_preference {
+                check_available(&policy, "energy_performance_available_preferences", epp)?;
+                write_if_changed(&policy.join("energy_performance_preference"), epp)?;
+            }
+            if let Some(max_freq) = profile.scaling_max_freq {
+                let max_freq = clamp_to_hardware(&policy, max_freq);
+                write_if_changed(&policy.join("scaling_max_freq"), &max_freq.to_string())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// What the first policy is actually running with.
+    pub fn current(&self) -> Option<CpuFreqState> {
+        let policy = self.policies().into_iter().next()?;
+        let boost = read_string(&self.cpufreq_dir().join("boost"))
+            .or_else(|| read_string(&policy.join("boost")))
+            .map(|b| b == "1");
+        Some(CpuFreqState {
+            governor: read_string(&policy.join("scaling_governor")),
+            energy_performance_preference: read_string(
+                &policy.join("energy_performance_preference"),
+            ),
+            boost,
+            scaling_max_freq: read_string(&policy.join("scaling_max_freq"))
+                .and_then(|f| f.parse().ok()),
+        })
+    }
+}
+
+/// The kernel silently clamps scaling_max_freq to the hardware range, so an
+/// out-of-range value would read back different and be rewritten every tick.
+fn clamp_to_hardware(policy: &Path, freq: u32) -> u32 {
+    let min = read::<u32>(&policy.join("cpuinfo_min_freq")).unwrap_or(0);
+    let max = read::<u32>(&policy.join("cpuinfo_max_freq")).unwrap_or(u32::MAX);
+    freq.clamp(min, max.max(min))
+}
+
+/// Rejects values the driver doesn't offer, with the list it does.
+fn check_available(policy: &Path, attr: &str, value: &str) -> Result<()> {
+    // Not every driver exposes the list; let the write itself decide then
+    let Some(available) = read_string(&policy.join(attr)) else {
+        return Ok(());
+    };
+    if !available.split_whitespace().any(|v| v == value) {
+        bail!("{:?} is not one of {}: {}", value, attr, available);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_sysfs::FakeSysfs;
+
+    const POLICY: &str = "sys/devices/system/cpu/cpufreq/policy0";
+
+    #[test]
+    fn max_freq_is_clamped_to_the_hardware_range() {
+        let sysfs = FakeSysfs::new("cpufreq-clamp");
+        sysfs
+            .write(&format!("{}/cpuinfo_min_freq", POLICY), "400000")
+            .write(&format!("{}/cpuinfo_max_freq", POLICY), "4000000");
+        let policy = sysfs.root().join(POLICY);
+
+        assert_eq!(clamp_to_hardware(&policy, 100_000), 400_000);
+        assert_eq!(clamp_to_hardware(&policy, 5_000_000), 4_000_000);
+        assert_eq!(clamp_to_hardware(&policy, 2_000_000), 2_000_000);
+    }
+
+    #[test]
+    fn max_freq_is_kept_without_a_hardware_range() {
+        let sysfs = FakeSysfs::new("cpufreq-no-range");
+        sysfs.write(&format!("{}/scaling_max_freq", POLICY), "4000000");
+        let policy = sysfs.root().join(POLICY);
+
+        assert_eq!(clamp_to_hardware(&policy, 5_000_000), 5_000_000);
+    }
+}
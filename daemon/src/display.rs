@@ -0,0 +1,243 @@
+use crate::config::{DisplayBackend, DisplayConfig};
+use crate::exec;
+use crate::log_once::LogOnce;
+use crate::plan;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// Wait before retrying a refresh rate the compositor refused.
+const RETRY_DELAY: Duration = Duration::from_secs(30);
+
+/// Switches the internal panel's refresh rate through the compositor.
+///
+/// The daemon runs as root without a Wayland session of its own, so every
+/// command is run as the logged-in user with that user's session env.
+pub struct DisplayActuator {
+    config: DisplayConfig,
+    last_rate: Option<u32>,
+    failed: Option<(u32, Instant)>,
+    errors: LogOnce,
+}
+
+/// Enough of a user's Wayland session to talk to their compositor.
+struct Session {
+    uid: u32,
+    gid: u32,
+    runtime_dir: PathBuf,
+    wayland_display: String,
+}
+
+/// The subset of `hyprctl -j monitors` we use.
+#[derive(Deserialize)]
+struct HyprMonitor {
+    name: String,
+    x: i32,
+    y: i32,
+    scale: f32,
+}
+
+impl DisplayActuator {
+    pub fn new(config: DisplayConfig) -> Self {
+        Self {
+            config,
+            last_rate: None,
+            failed: None,
+            errors: LogOnce::default(),
+        }
+    }
+
+    /// Sets the refresh rate if it differs from the last one we applied.
+    pub async fn apply(&mut self, refresh_rate: u32) {
+        if self.last_rate == Some(refresh_rate) {
+            return;
+        }
+        // Don't run a failing command every tick
+        if let Some((rate, at)) = self.failed {
+            if rate == refresh_rate && at.elapsed() < RETRY_DELAY {
+                return;
+            }
+        }
+
+        // No session yet (e.g. still at the login screen): try again next tick
+        let Some(session) = find_session(self.config.uid) else {
+            log::debug!("No Wayland session found, not setting refresh rate");
+            return;
+        };
+
+        let result = self.run(&session, refresh_rate).await;
+        if result.is_ok() {
+            self.last_rate = Some(refresh_rate);
+            self.failed = None;
+            log::info!(
+                "Set {} to {}@{}Hz via {:?}",
+                self.config.output,
+                self.config.mode,
+                refresh_rate,
+                self.config.backend
+            );
+        } else {
+            self.failed = Some((refresh_rate, Instant::now()));
+        }
+        self.errors.report("set refresh rate", result);
+    }
+
+    /// Why the last attempt to set the rate failed, until one succeeds.
+    pub fn last_error(&self) -> Option<&str> {
+        self.errors.last_error()
+    }
+
+    /// Forgets the applied rate, so the next `apply` sets it again.
+    pub fn reset(&mut self) {
+        self.last_rate = None;
+        self.failed = None;
+    }
+
+    async fn run(&self, session: &Session, rate: u32) -> Result<()> {
+        let output = &self.config.output;
+        let mode = &self.config.mode;
+
+        let mut cmd = match self.config.backend {
+            DisplayBackend::Niri => {
+                let mut cmd = Command::new("niri");
+                cmd.args(["msg", "output", output, "mode"])
+                    .arg(format!("{}@{}.000", mode, rate));
+                if let Some(socket) = find_socket(&session.runtime_dir, "niri.", ".sock") {
+                    cmd.env("NIRI_SOCKET", socket);
+                }
+                cmd
+            }
+            DisplayBackend::Sway => {
+                let mut cmd = Command::new("swaymsg");
+                cmd.args(["output", output, "mode"])
+                    .arg(format!("{}@{}Hz", mode, rate));
+                if let Some(socket) = find_socket(&session.runtime_dir, "sway-ipc.", ".sock") {
+                    cmd.env("SWAYSOCK", socket);
+                }
+                cmd
+            }
+            DisplayBackend::Hyprland => {
+                // A monitor rule replaces position and scale too, so keep them
+                let (position, scale) = self.hyprland_placement(session).await?;
+                let mut cmd = hyprctl(session);
+                cmd.args(["keyword", "monitor"]).arg(format!(
+                    "{},{}@{},{},{}",
+                    output, mode, rate, position, scale
+                ));
+                cmd
+            }
+            DisplayBackend::WlrRandr => {
+                let mut cmd = Command::new("wlr-randr");
+                cmd.args(["--output", output, "--mode"])
+                    .arg(format!("{}@{}Hz", mode, rate));
+                cmd
+            }
+        };
+
+        session.prepare(&mut cmd);
+        if !plan::allow_command(&cmd) {
+            return Ok(());
+        }
+        let result = exec::output(&mut cmd, exec::TIMEOUT).await?;
+        if !result.status.success() {
+            bail!(
+                "{:?} failed: {}",
+                cmd.as_std().get_program(),
+                String::from_utf8_lossy(&result.stderr).trim()
+            );
+        }
+        Ok(())
+    }
+
+    /// Position and scale from the config, or the output's current ones.
+    async fn hyprland_placement(&self, session: &Session) -> Result<(String, String)> {
+        if let (Some(position), Some(scale)) = (&self.config.position, self.config.scale) {
+            return Ok((position.clone(), scale.to_string()));
+        }
+        let mut cmd = hyprctl(session);
+        cmd.args(["-j", "monitors"]);
+        session.prepare(&mut cmd);
+        let result = exec::output(&mut cmd, exec::TIMEOUT).await?;
+        let monitors: Vec<HyprMonitor> =
+            serde_json::from_slice(&result.stdout).context("Unexpected hyprctl monitors reply")?;
+        let Some(monitor) = monitors.iter().find(|m| m.name == self.config.output) else {
+            bail!("Hyprland has no output {}", self.config.output);
+        };
+        Ok((
+            self.config
+                .position
+                .clone()
+                .unwrap_or_else(|| format!("{}x{}", monitor.x, monitor.y)),
+            self.config.scale.unwrap_or(monitor.scale).to_string(),
+        ))
+    }
+}
+
+impl Session {
+    /// Runs `cmd` as the session's user, inside its Wayland session.
+    fn prepare(&self, cmd: &mut Command) {
+        cmd.uid(self.uid)
+            .gid(self.gid)
+            .env("XDG_RUNTIME_DIR", &self.runtime_dir)
+            .env("WAYLAND_DISPLAY", &self.wayland_display);
+    }
+}
+
+fn hyprctl(session: &Session) -> Command {
+    let mut cmd = Command::new("hyprctl");
+    if let Some(instance) = find_socket(&session.runtime_dir.join("hypr"), "", "") {
+        if let Some(name) = instance.file_name() {
+            cmd.env("HYPRLAND_INSTANCE_SIGNATURE", name);
+        }
+    }
+    cmd
+}
+
+/// Finds a user with a live Wayland socket in `/run/user/<uid>`.
+/// With `uid` set only that user is considered, otherwise the first regular
+/// (uid >= 1000) user with a session wins.
+fn find_session(uid: Option<u32>) -> Option<Session> {
+    let mut runtime_dirs: Vec<PathBuf> = fs::read_dir("/run/user")
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .collect();
+    runtime_dirs.sort();
+
+    runtime_dirs.into_iter().find_map(|runtime_dir| {
+        let meta = fs::metadata(&runtime_dir).ok()?;
+        match uid {
+            Some(uid) if meta.uid() != uid => return None,
+            None if meta.uid() < 1000 => return None,
+            _ => {}
+        }
+        let socket = find_socket(&runtime_dir, "wayland-", "")?;
+        Some(Session {
+            uid: meta.uid(),
+            gid: meta.gid(),
+            wayland_display: socket.file_name()?.to_string_lossy().into_owned(),
+            runtime_dir,
+        })
+    })
+}
+
+/// First entry in `dir` named `<prefix>...<suffix>`, skipping `.lock` files.
+fn find_socket(dir: &Path, prefix: &str, suffix: &str) -> Option<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|path| {
+            let name = path.file_name().map(|n| n.to_string_lossy().into_owned());
+            name.is_some_and(|n| {
+                n.starts_with(prefix) && n.ends_with(suffix) && !n.ends_with(".lock")
+            })
+        })
+        .collect();
+    entries.sort();
+    entries.into_iter().next()
+}
@@ -0,0 +1,227 @@
+use crate::sensors::PowerSupplySummary;
+use nitro_core::BatteryStatus;
+use std::time::Instant;
+
+/// Weight of a new sample in the moving average. Low enough that a
+/// compile burst doesn't halve the estimate, high enough to follow a
+/// profile switch within a minute or so.
+const ALPHA: f32 = 0.15;
+
+/// Rates below this are treated as "not really charging/discharging".
+const MIN_RATE_WATTS: f32 = 0.1;
+
+/// Smoothed time-to-empty / time-to-full estimator.
+///
+/// Blends two signals into one EWMA of the battery rate (Watts):
+/// `power_now` every tick, and the slope of `energy_now` whenever the
+/// firmware updates it (which it does in coarse steps, every few ticks).
+#[derive(Default)]
+pub struct RuntimeEstimator {
+    rate: Option<f32>,
+    /// Last time `energy_now` changed, and its value then (Wh).
+    anchor: Option<(Instant, f32)>,
+    last_plugged_in: Option<bool>,
+}
+
+/// Seconds until empty (on battery) or full (on AC, while charging).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RuntimeEstimate {
+    pub time_to_empty: Option<u64>,
+    pub time_to_full: Option<u64>,
+}
+
+impl RuntimeEstimator {
+    /// `charge_limit` is the percentage charging stops at, if capped.
+    pub fn update(
+        &mut self,
+        supplies: &PowerSupplySummary,
+        charge_limit: Option<u8>,
+    ) -> RuntimeEstimate {
+        self.update_at(supplies, charge_limit, Instant::now())
+    }
+
+    fn update_at(
+        &mut self,
+        supplies: &PowerSupplySummary,
+        charge_limit: Option<u8>,
+        now: Instant,
+    ) -> RuntimeEstimate {
+        // Rate from the old power source says nothing about the new one.
+        if self.last_plugged_in != Some(supplies.is_plugged_in) {
+            self.rate = None;
+            self.anchor = None;
+            self.last_plugged_in = Some(supplies.is_plugged_in);
+        }
+
+        let energy_now: Option<f32> = supplies.batteries.iter().map(|b| b.energy_now_wh).sum();
+        let energy_full: Option<f32> = supplies.batteries.iter().map(|b| b.energy_full_wh).sum();
+
+        self.add_sample(supplies.battery_watts);
+
+        if let Some(now_wh) = energy_now {
+            match self.anchor {
+                Some((then, then_wh)) if then_wh != now_wh => {
+                    let hours = now.duration_since(then).as_secs_f32() / 3600.0;
+                    if hours > 0.0 {
+                        self.add_sample((now_wh - then_wh).abs() / hours);
+                    }
+                    self.anchor = Some((now, now_wh));
+                }
+                Some(_) => {}
+                None => self.anchor = Some((now, now_wh)),
+            }
+        }
+
+        let (Some(rate), Some(now_wh)) = (self.rate, energy_now) else {
+            return RuntimeEstimate::default();
+        };
+        if rate < MIN_RATE_WATTS {
+            return RuntimeEstimate::default();
+        }
+
+        let secs = |wh: f32| Some((wh.max(0.0) / rate * 3600.0) as u64);
+        if supplies.is_plugged_in {
+            // Plugged in but "Not charging" or "Full" (e.g. held at a charge
+            // limit): there is nothing to count down to
+            let charging = supplies
+                .batteries
+                .iter()
+                .any(|b| b.status == BatteryStatus::Charging);
+            let target = energy_full
+                .map(|full| full * f32::from(charge_limit.unwrap_or(100).min(100)) / 100.0)
+                .filter(|target| *target > now_wh);
+            RuntimeEstimate {
+                time_to_empty: None,
+                time_to_full: target.filter(|_| charging).and_then(|t| secs(t - now_wh)),
+            }
+        } else {
+            RuntimeEstimate {
+                time_to_empty: secs(now_wh),
+                time_to_full: None,
+            }
+        }
+    }
+
+    fn add_sample(&mut self, watts: f32) {
+        let watts = watts.abs();
+        self.rate = Some(match self.rate {
+            Some(rate) => rate + ALPHA * (watts - rate),
+            None => watts,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nitro_core::BatteryInfo;
+    use std::time::Duration;
+
+    fn supplies(
+        plugged_in: bool,
+        status: BatteryStatus,
+        watts: f32,
+        now_wh: f32,
+    ) -> PowerSupplySummary {
+        PowerSupplySummary {
+            battery_watts: watts,
+            battery_percent: (now_wh / 50.0 * 100.0) as u8,
+            is_plugged_in: plugged_in,
+            batteries: vec![BatteryInfo {
+                name: "BAT0".to_string(),
+                status,
+                energy_now_wh: Some(now_wh),
+                energy_full_wh: Some(50.0),
+                ..BatteryInfo::default()
+            }],
+        }
+    }
+
+    #[test]
+    fn time_to_empty_from_power_now() {
+        let mut estimator = RuntimeEstimator::default();
+        let estimate = estimator.update_at(
+            &supplies(false, BatteryStatus::Discharging, 10.0, 25.0),
+            None,
+            Instant::now(),
+        );
+        assert_eq!(estimate.time_to_empty, Some(9000));
+        assert_eq!(estimate.time_to_full, None);
+    }
+
+    #[test]
+    fn energy_steps_feed_the_rate_only_when_energy_changes() {
+        let mut estimator = RuntimeEstimator::default();
+        let start = Instant::now();
+        let discharging = |wh| supplies(false, BatteryStatus::Discharging, 10.0, wh);
+
+        estimator.update_at(&discharging(25.0), None, start);
+        // Firmware hasn't updated energy_now yet: only power_now counts
+        estimator.update_at(&discharging(25.0), None, start + Duration::from_secs(30));
+        assert_eq!(estimator.rate, Some(10.0));
+
+        // 0.5 Wh in 60 s is 30 W, blended into the average
+        estimator.update_at(&discharging(24.5), None, start + Duration::from_secs(60));
+        let expected = 10.0 + ALPHA * (30.0 - 10.0);
+        assert!((estimator.rate.unwrap() - expected).abs() < 0.01);
+        assert_eq!(
+            estimator.anchor.map(|(_, wh)| wh),
+            Some(24.5),
+            "anchor follows the new step"
+        );
+    }
+
+    #[test]
+    fn plug_change_resets_the_average() {
+        let mut estimator = RuntimeEstimator::default();
+        let now = Instant::now();
+        estimator.update_at(
+            &supplies(false, BatteryStatus::Discharging, 40.0, 25.0),
+            None,
+            now,
+        );
+        let estimate = estimator.update_at(
+            &supplies(true, BatteryStatus::Charging, 5.0, 25.0),
+            None,
+            now + Duration::from_secs(2),
+        );
+        // Only the charger's 5 W, none of the 40 W discharge
+        assert_eq!(estimator.rate, Some(5.0));
+        assert_eq!(estimate.time_to_full, Some(18000));
+    }
+
+    #[test]
+    fn tiny_rates_give_no_estimate() {
+        let mut estimator = RuntimeEstimator::default();
+        let estimate = estimator.update_at(
+            &supplies(
+                false,
+                BatteryStatus::Discharging,
+                MIN_RATE_WATTS / 2.0,
+                25.0,
+            ),
+            None,
+            Instant::now(),
+        );
+        assert_eq!(estimate.time_to_empty, None);
+    }
+
+    #[test]
+    fn time_to_full_stops_at_the_charge_limit() {
+        let mut estimator = RuntimeEstimator::default();
+        let estimate = estimator.update_at(
+            &supplies(true, BatteryStatus::Charging, 10.0, 25.0),
+            Some(80),
+            Instant::now(),
+        );
+        // 40 Wh of 50 Wh at 80%, 15 Wh to go at 10 W
+        assert_eq!(estimate.time_to_full, Some(5400));
+
+        let estimate = estimator.update_at(
+            &supplies(true, BatteryStatus::NotCharging, 10.0, 40.0),
+            Some(80),
+            Instant::now(),
+        );
+        assert_eq!(estimate.time_to_full, None);
+    }
+}
@@ -0,0 +1,29 @@
+use anyhow::{bail, Context, Result};
+use std::process::Output;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::time;
+
+/// Default for quick CLI tools (ryzenadj, compositor IPC).
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+static SPAWNED: AtomicU64 = AtomicU64::new(0);
+
+/// Runs a command to completion without blocking the runtime. A child that
+/// is still running after `timeout` is killed and reported as an error.
+pub async fn output(cmd: &mut Command, timeout: Duration) -> Result<Output> {
+    SPAWNED.fetch_add(1, Ordering::Relaxed);
+    let program = cmd.as_std().get_program().to_string_lossy().into_owned();
+    // Dropping the output future on timeout then kills the child
+    cmd.kill_on_drop(true);
+    match time::timeout(timeout, cmd.output()).await {
+        Ok(output) => output.with_context(|| format!("Failed to execute {}", program)),
+        Err(_) => bail!("{} did not finish within {:?}, killed it", program, timeout),
+    }
+}
+
+/// Child processes started since the daemon came up.
+pub fn spawned() -> u64 {
+    SPAWNED.load(Ordering::Relaxed)
+}
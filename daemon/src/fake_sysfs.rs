@@ -0,0 +1,50 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// A throwaway sysfs/procfs tree under the temp dir, for `sysfs_root`.
+pub struct FakeSysfs {
+    root: PathBuf,
+}
+
+impl FakeSysfs {
+    /// `name` only has to be unique among tests running at the same time.
+    pub fn new(name: &str) -> Self {
+        let root = std::env::temp_dir().join(format!("nitro-test-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&root);
+        fs::create_dir_all(&root).unwrap();
+        Self { root }
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Writes `value` to `path` (relative to the root), creating directories.
+    pub fn write(&self, path: &str, value: &str) -> &Self {
+        let path = self.root.join(path);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, format!("{}\n", value)).unwrap();
+        self
+    }
+
+    /// Contents of `path`, trimmed. None if it doesn't exist.
+    pub fn read(&self, path: &str) -> Option<String> {
+        fs::read_to_string(self.root.join(path))
+            .ok()
+            .map(|s| s.trim().to_string())
+    }
+
+    /// A `/sys/class/power_supply/<name>` device with the given attributes.
+    pub fn supply(&self, name: &str, attrs: &[(&str, &str)]) -> &Self {
+        for (attr, value) in attrs {
+            self.write(&format!("sys/class/power_supply/{}/{}", name, attr), value);
+        }
+        self
+    }
+}
+
+impl Drop for FakeSysfs {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::attr;
+use crate::config::GpuMode;
+use crate::exec;
+use crate::plan;
+use anyhow::{bail, Result};
+use nitro_core::GpuState;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// supergfxd may have to unload and reload driver modules for a switch.
+const SWITCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// First wait before checking again whether the dGPU is still in use; it
+/// doubles on every blocked attempt up to the paranoid interval.
+const FIRST_RETRY: Duration = Duration::from_secs(4);
+
+/// Keeps the discrete GPU in the profile's mode, via `supergfxctl` or, when
+/// that isn't available, PCI runtime power management.
+pub struct GpuSwitch {
+    root: PathBuf,
+    applied: Option<GpuMode>,
+    blockers: Vec<u32>,
+    // Each blocked attempt forks supergfxctl and scans every process's fds
+    retry_at: Option<(GpuMode, Instant)>,
+    retry_delay: Duration,
+    max_retry_delay: Duration,
+    last_error: Option<String>,
+}
+
+impl GpuSwitch {
+    pub fn new(root: impl Into<PathBuf>, max_retry_delay: Duration) -> Self {
+        Self {
+            root: root.into(),
+            applied: None,
+            blockers: Vec::new(),
+            retry_at: None,
+            retry_delay: FIRST_RETRY,
+            max_retry_delay,
+            last_error: None,
+        }
+    }
+
+    /// PIDs holding `/dev/nvidia*` open that kept the last switch from happening.
+    pub fn blockers(&self) -> &[u32] {
+        &self.blockers
+    }
+
+    /// Why the last switch failed, until one succeeds.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+
+    /// Forgets the applied mode, so the next `apply` checks it again.
+    pub fn reset(&mut self) {
+        self.applied = None;
+        self.retry_at = None;
+        self.retry_delay = FIRST_RETRY;
+    }
+
+    /// Switches to `mode` unless it is already active. While processes use
+    /// the dGPU nothing is changed; the switch is retried with a growing delay.
+    pub async fn apply(&mut self, mode: GpuMode, gpus: &[GpuState]) -> Result<()> {
+        if self.applied == Some(mode) {
+            return Ok(());
+        }
+        match self.retry_at {
+            Some((blocked, at)) if blocked == mode && Instant::now() < at => return Ok(()),
+            Some((blocked, _)) if blocked != mode => self.retry_delay = FIRST_RETRY,
+            _ => {}
+        }
+
+        let supergfx = supergfx_mode().await;
+        // Without supergfxctl both modes mean "let the dGPU suspend itself"
+        let not_auto: Vec<PathBuf> = gpus
+            .iter()
+            .map(|gpu| self.root.join("sys/bus/pci/devices").join(&gpu.pci_address))
+            .filter(|dev| {
+                attr::read_string(&dev.join("power/control")).is_some_and(|c| c != "auto")
+            })
+            .collect();
+        let up_to_date = match &supergfx {
+            Some(current) => current.eq_ignore_ascii_case(mode.as_str()),
+            None => not_auto.is_empty(),
+        };
+
+        if !up_to_date {
+            let blockers = self.nvidia_users();
+            if !blockers.is_empty() && blockers != self.blockers {
+                log::warn!(
+                    "Not switching dGPU to {}: /dev/nvidia* is open by PIDs {:?}",
+                    mode.as_str(),
+                    blockers
+                );
+            }
+            self.blockers = blockers;
+            if !self.blockers.is_empty() {
+                self.retry_at = Some((mode, Instant::now() + self.retry_delay));
+                self.retry_delay = (self.retry_delay * 2).min(self.max_retry_delay);
+                return Ok(());
+            }
+        }
+        self.retry_at = None;
+        self.retry_delay = FIRST_RETRY;
+
+        // Don't retry a failing switch every tick; the next mode change will
+        self.applied = Some(mode);
+        let result = if up_to_date {
+            Ok(())
+        } else {
+            self.switch(mode, supergfx.is_some(), not_auto).await
+        };
+        self.last_error = result.as_ref().err().map(|e| format!("{:#}", e));
+        result
+    }
+
+    async fn switch(&self, mode: GpuMode, supergfx: bool, not_auto: Vec<PathBuf>) -> Result<()> {
+        if supergfx {
+            log::info!("Switching dGPU to {} via supergfxctl", mode.as_str());
+            let mut cmd = Command::new("supergfxctl");
+            cmd.args(["-m", mode.as_str()]);
+            if !plan::allow_command(&cmd) {
+                return Ok(());
+            }
+            let output = exec::output(&mut cmd, SWITCH_TIMEOUT).await?;
+            if !output.status.success() {
+                bail!(
+                    "supergfxctl failed: {}",
+                    String::from_utf8_lossy(&output.stderr).trim()
+                );
+            }
+        } else {
+            for dev in not_auto {
+                log::info!("Enabling runtime PM for {}", dev.display());
+                attr::write(&dev.join("power/control"), "auto")?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes with an open file descriptor on an NVIDIA device node.
+    fn nvidia_users(&self) -> Vec<u32> {
+        let Ok(entries) = fs::read_dir(self.root.join("proc")) else {
+            return Vec::new();
+        };
+        let mut pids: Vec<u32> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                // fd directories of other users' processes need root; skip on error
+                let holds_gpu = fs::read_dir(entry.path().join("fd"))
+                    .ok()?
+                    .flatten()
+                    .filter_map(|fd| fs::read_link(fd.path()).ok())
+                    .any(|target| target.to_string_lossy().starts_with("/dev/nvidia"));
+                holds_gpu.then_some(pid)
+            })
+            .collect();
+        pids.sort_unstable();
+        pids
+    }
+}
+
+/// Current mode as reported by supergfxd, or None if it isn't running.
+async fn supergfx_mode() -> Option<String> {
+    let output = exec::output(Command::new("supergfxctl").arg("-g"), exec::TIMEOUT)
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
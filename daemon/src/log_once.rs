@@ -0,0 +1,28 @@
+use anyhow::Result;
+
+/// Logs a failing per-tick operation once per distinct error instead of on
+/// every tick, and again only after it succeeded in between.
+#[derive(Default)]
+pub struct LogOnce {
+    last_error: Option<String>,
+}
+
+impl LogOnce {
+    pub fn report(&mut self, what: &str, result: Result<()>) {
+        match result {
+            Ok(()) => self.last_error = None,
+            Err(e) => {
+                let message = e.to_string();
+                if self.last_error.as_ref() != Some(&message) {
+                    log::error!("Failed to {}: {}", what, message);
+                    self.last_error = Some(message);
+                }
+            }
+        }
+    }
+
+    /// The error still standing, if the last attempt failed.
+    pub fn last_error(&self) -> Option<&str> {
+        self.last_error.as_deref()
+    }
+}
@@ -1,52 +1,306 @@
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 mod actuator;
+mod attr;
+mod charge;
 mod config;
+mod containers;
+mod cpufreq;
+mod display;
+mod estimator;
+mod exec;
+#[cfg(test)]
+mod fake_sysfs;
+mod gpu;
+mod log_once;
+mod persist;
+mod plan;
+mod platform;
+mod resume;
+mod rfkill;
+mod ryzen_info;
+mod sensors;
+mod stats;
+mod uevent;
+mod worker;
 use config::NitroConfig;
-use nitro_core::{DaemonCommand, PowerState, Profile};
-use regex::Regex;
+use nitro_core::{
+    DaemonCommand, DaemonResponse, DaemonStats, PowerState, ProcessPower, Profile, Temperature,
+};
+use sensors::{
+    radio_state, CpuPowerMeter, CpuUtilTracker, PowerSupplySummary, ProcessTracker, SensorBackend,
+    SysfsBackend,
+};
 use std::fs;
-use std::process::Command;
 use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
 use tokio::net::UnixListener;
-use tokio::sync::watch;
+use tokio::sync::{mpsc, watch};
 use tokio::time;
 
+/// State shared between the governor loop and IPC clients.
+#[derive(Default)]
+struct Shared {
+    profile: Mutex<Profile>,
+    top_consumers: Mutex<Vec<ProcessPower>>,
+}
+
 async fn run_loop(
     tx: watch::Sender<PowerState>,
-    shared_profile: Arc<Mutex<Profile>>,
+    mut cmd_rx: mpsc::Receiver<DaemonCommand>,
+    shared: Arc<Shared>,
     config: NitroConfig,
 ) -> Result<()> {
     let mut interval = time::interval(Duration::from_secs(2));
-    let mut actuator = actuator::Actuator::new(config);
+    // After a long stall, tick once and carry on; replaying a burst of
+    // missed ticks would just fork ryzenadj several times in a row.
+    interval.set_missed_tick_behavior(time::MissedTickBehavior::Skip);
+    let mut resume = resume::ResumeDetector::new();
+    let (resume_tx, mut resume_rx) = mpsc::channel::<()>(1);
+    resume::spawn_watcher(resume_tx);
+    // Polling catches plug changes within 2 s; uevents make it immediate
+    let (uevent_tx, mut uevent_rx) = mpsc::channel::<uevent::AdapterEvent>(10);
+    uevent::spawn_listener(uevent_tx);
+    let sensors: Box<dyn SensorBackend> = Box::new(SysfsBackend::new(&config.sysfs_root));
+    let track_wakeups = config.track_wakeups;
+    let rfkill = rfkill::RfkillSwitch::new(&config.sysfs_root);
+    let charge = charge::ChargeLimiter::new(&config.sysfs_root);
+    let mut platform = platform::PlatformTuner::new(&config.sysfs_root);
+    let mut cpufreq = cpufreq::CpuFreq::new(&config.sysfs_root);
+    let store = persist::StateStore::new(&config.state_file);
+    // Shared with the container task, which records what it paused
+    let persisted = Arc::new(Mutex::new(store.load()));
+    // Last limit we tried to write, so a failing write isn't retried every tick
+    let mut attempted_charge_limit: Option<u8> = None;
+    if let Some(limit) = persisted.lock().unwrap().charge_limit {
+        log::info!(
+            "Restoring charge limit {}% from {}",
+            limit,
+            store.path().display()
+        );
+    }
+    // Power limits, refresh rate and dGPU mode are set by a worker task
+    let (target_tx, target_rx) = watch::channel(None);
+    let (report_tx, report_rx) = watch::channel(worker::HardwareReport::default());
+    worker::spawn(config.clone(), target_rx, report_tx);
+    // Containers are paused and unpaused on a task of their own
+    let (container_tx, container_rx) = watch::channel(None);
+    let (unpause_tx, unpause_rx) = mpsc::channel::<()>(1);
+    containers::spawn(
+        config.containers.clone(),
+        store.clone(),
+        persisted.clone(),
+        container_rx,
+        unpause_rx,
+    );
+    let mut resumes = 0;
+    let mut wakeups = stats::WakeupMeter::default();
+    let mut cpu_util = CpuUtilTracker::default();
+    let mut cpu_power = CpuPowerMeter::default();
+    let mut runtime = estimator::RuntimeEstimator::default();
+    let mut processes = ProcessTracker::default();
+    let mut ryzen_reader = ryzen_info::BackgroundReader::default();
+
+    for supply in sensors.power_supplies() {
+        log::info!(
+            "Found power supply {} ({:?}{})",
+            supply.name,
+            supply.kind,
+            if supply.is_system { "" } else { ", peripheral" }
+        );
+    }
 
     loop {
-        interval.tick().await;
+        // Sleep until the next tick, or wake early on a plug/unplug uevent or
+        // a command that needs the hardware (the state is then re-read and
+        // published right away).
+        tokio::select! {
+            _ = interval.tick() => {}
+            Some(event) = uevent_rx.recv() => {
+                log::info!(
+                    "Adapter {} {}",
+                    event.name,
+                    match event.online {
+                        Some(true) => "plugged in",
+                        Some(false) => "unplugged",
+                        None => "changed",
+                    }
+                );
+                // Start a fresh 2 s period from this reading
+                interval.reset();
+            }
+            // Resuming sets the wall clock; ResumeDetector below confirms it
+            Some(()) = resume_rx.recv() => {
+                log::debug!("Wall clock was set, checking for a resume");
+                interval.reset();
+            }
+            Some(cmd) = cmd_rx.recv() => {
+                let radios = sensors.rfkill_devices();
+                let result = match cmd {
+                    DaemonCommand::ToggleWifi => rfkill.toggle("wlan", &radio_state(&radios, "wlan")),
+                    DaemonCommand::ToggleBluetooth => {
+                        rfkill.toggle("bluetooth", &radio_state(&radios, "bluetooth"))
+                    }
+                    DaemonCommand::SetChargeLimit(Some(limit)) if !(1..=100).contains(&limit) => {
+                        Err(anyhow!("Charge limit {}% is out of range", limit))
+                    }
+                    DaemonCommand::SetChargeLimit(limit) => {
+                        let mut persisted = persisted.lock().unwrap();
+                        persisted.charge_limit = limit;
+                        attempted_charge_limit = None;
+                        store.save(&persisted)
+                    }
+                    DaemonCommand::ResumeContainers => {
+                        // A full channel means a resume is already on its way
+                        let _ = unpause_tx.try_send(());
+                        Ok(())
+                    }
+                    _ => Ok(()),
+                };
+                if let Err(e) = result {
+                    log::error!("{:?} failed: {}", cmd, e);
+                }
+            }
+        }
 
-        let battery_watts = read_watts();
-        let cpu_watts = read_cpu_watts();
-        let cpu_load = read_cpu_load();
-        let battery_percent = read_battery_percent();
-        let is_plugged_in = read_is_plugged_in();
+        if let Some(slept) = resume.check() {
+            log::info!("Resumed from suspend after {:?}, reapplying profile", slept);
+            resumes += 1;
+            attempted_charge_limit = None;
+        }
+
+        let supplies = PowerSupplySummary::from_supplies(&sensors.power_supplies());
+        let battery_watts = supplies.battery_watts;
+        // Energy counters are free to read, the SMU table nearly so. Only fork
+        // ryzenadj when neither is usable (e.g. unknown pm_table version).
+        // Whatever was read also serves the power limit drift check.
+        let energy_watts = cpu_power.update(sensors.energy_counters());
+        // ryzenadj runs in the background; its newest result is up to a tick old.
+        let readings = match sensors.pm_table().and_then(|table| table.decode()) {
+            Some(info) => Some((Instant::now(), info)),
+            None if energy_watts.is_none() => ryzen_reader.latest().await,
+            None => None,
+        };
+        let readings_at = readings.as_ref().map(|(at, _)| *at);
+        let readings = readings.map(|(_, info)| info);
+        let cpu_watts = energy_watts
+            .or_else(|| readings.as_ref().and_then(|info| info.ppt_fast.value))
+            .unwrap_or(0.0);
+        let cpu_load = sensors.load_average();
+        let cpu_times = sensors.cpu_times();
+        let util = cpu_util.update(&cpu_times);
+        let battery_percent = supplies.battery_percent;
+        let is_plugged_in = supplies.is_plugged_in;
+        let estimate = runtime.update(&supplies, charge.current());
+        let consumers = match cpu_times.first() {
+            Some(total) => processes.update(sensors.processes(track_wakeups), total, cpu_watts),
+            None => Vec::new(),
+        };
+        *shared.top_consumers.lock().unwrap() = consumers;
 
         // Read the actual target profile from shared state
         let current_profile = {
-            let lock = shared_profile.lock().unwrap();
+            let lock = shared.profile.lock().unwrap();
             lock.clone()
         };
 
-        // Apply Hardware Limits
-        actuator.apply_profile(&current_profile, is_plugged_in);
+        // Hand the hardware limits to the worker without waiting for it
+        let gpus = sensors.discrete_gpus();
+        target_tx.send_replace(Some(worker::HardwareTarget {
+            profile: current_profile.clone(),
+            is_plugged_in,
+            readings: readings.clone(),
+            readings_at,
+            gpus: gpus.clone(),
+            resumes,
+        }));
+
+        // Platform and cpufreq follow the effective profile (Pro on AC)
+        let target = actuator::target_profile(&current_profile, is_plugged_in);
+        // A new amd_pstate mode recreates the cpufreq policies, so it goes first
+        platform.apply(config.profile(&target));
+        cpufreq.apply(config.profile(&target));
+
+        // Charge threshold: a limit set over IPC wins over the profile's
+        // and once neither asks for one, a limit we set earlier is lifted.
+        let wanted_charge_limit = persisted
+            .lock()
+            .unwrap()
+            .charge_limit
+            .or(config.profile(&current_profile).charge_limit)
+            .or(attempted_charge_limit
+                .filter(|limit| *limit < 100)
+                .map(|_| 100));
+        if let Some(limit) = wanted_charge_limit {
+            if attempted_charge_limit != Some(limit) {
+                attempted_charge_limit = Some(limit);
+                if let Err(e) = charge.apply(limit) {
+                    log::error!("Failed to set charge limit: {}", e);
+                }
+            }
+        }
+
+        container_tx.send_replace(Some(containers::ContainerTarget {
+            cpu_util_percent: util.total,
+            is_plugged_in,
+        }));
+
+        let mut temperatures = sensors.temperatures();
+        // Without k10temp (or zenpower) loaded, the SMU still knows Tctl
+        if !temperatures.iter().any(|t| t.label == "Tctl") {
+            if let Some(tctl) = readings.as_ref().and_then(|info| info.thm_core.value) {
+                temperatures.push(Temperature {
+                    label: "Tctl".to_string(),
+                    celsius: tctl,
+                });
+            }
+        }
+
+        let radios = sensors.rfkill_devices();
+        let wifi = radio_state(&radios, "wlan");
+        let bluetooth = radio_state(&radios, "bluetooth");
 
+        let report = report_rx.borrow().clone();
         let state = PowerState {
             battery_watts,
             cpu_watts,
             battery_percent,
             cpu_load,
+            cpu_util_percent: util.total,
+            per_core_util: util.per_core,
+            temperatures,
+            batteries: supplies.batteries,
+            time_to_empty: estimate.time_to_empty,
+            time_to_full: estimate.time_to_full,
+            dgpu_state: gpus.into_iter().next(),
+            dgpu_blockers: report.dgpu_blockers,
             profile: current_profile,
-            wifi_on: true,      // Placeholder
-            bluetooth_on: true, // Placeholder
+            wifi_on: wifi.is_on(),
+            bluetooth_on: bluetooth.is_on(),
+            wifi,
+            bluetooth,
+            charge_limit: charge.current(),
+            limits_in_sync: report.limits_in_sync,
+            last_revert_at: report.last_revert_at,
+            revert_count: report.revert_count,
+            hardware_error: report.last_error,
+            daemon_stats: DaemonStats {
+                limit_applies: report.limit_applies,
+                skipped_applies: report.skipped_applies,
+                processes_spawned: exec::spawned(),
+                wakeups_per_sec: wakeups.update(),
+            },
+            dry_run: plan::is_dry_run(),
+            cpufreq: cpufreq.current(),
+            amd_pstate: platform.amd_pstate(),
+            platform_profile: platform.platform_profile(),
+            paused_containers: persisted
+                .lock()
+                .unwrap()
+                .paused_containers
+                .iter()
+                .map(|c| c.name.clone())
+                .collect(),
             is_plugged_in,
         };
 
@@ -57,7 +311,8 @@ async fn run_loop(
 
 async fn start_ipc_server(
     rx: watch::Receiver<PowerState>,
-    shared_profile: Arc<Mutex<Profile>>,
+    cmd_tx: mpsc::Sender<DaemonCommand>,
+    shared: Arc<Shared>,
 ) -> Result<()> {
     let socket_path = "/tmp/nitro.sock";
     if fs::metadata(socket_path).is_ok() {
@@ -76,12 +331,15 @@ async fn start_ipc_server(
     loop {
         let (socket, _) = listener.accept().await?;
         let mut rx = rx.clone();
-        let shared_profile = shared_profile.clone();
+        let cmd_tx = cmd_tx.clone();
+        let shared = shared.clone();
 
         tokio::spawn(async move {
             let (reader, mut writer) = socket.into_split();
+            // Replies to query commands, interleaved with the state stream
+            let (resp_tx, mut resp_rx) = mpsc::channel::<DaemonResponse>(10);
 
-            // Task 1: Writer (Send PowerState)
+            // Task 1: Writer (Send PowerState and DaemonResponse)
             let writer_task = tokio::spawn(async move {
                 // Send the current value immediately
                 {
@@ -97,10 +355,22 @@ async fn start_ipc_server(
                     }
                 }
 
-                // Watch for changes
-                while rx.changed().await.is_ok() {
-                    let state = rx.borrow().clone();
-                    if let Ok(json) = serde_json::to_string(&state) {
+                // Watch for changes and replies
+                loop {
+                    let json = tokio::select! {
+                        changed = rx.changed() => {
+                            if changed.is_err() {
+                                break;
+                            }
+                            let state = rx.borrow().clone();
+                            serde_json::to_string(&state)
+                        }
+                        response = resp_rx.recv() => match response {
+                            Some(response) => serde_json::to_string(&response),
+                            None => break,
+                        },
+                    };
+                    if let Ok(json) = json {
                         if writer
                             .write_all(format!("{}\n", json).as_bytes())
                             .await
@@ -120,14 +390,46 @@ async fn start_ipc_server(
                         log::info!("Received Command: {:?}", cmd);
                         match cmd {
                             DaemonCommand::SetProfile(p) => {
-                                let mut lock = shared_profile.lock().unwrap();
+                                let mut lock = shared.profile.lock().unwrap();
                                 *lock = p;
                             }
-                            DaemonCommand::ToggleWifi => {
-                                log::info!("Toggle Wifi (Not Implemented)");
+                            DaemonCommand::ToggleWifi
+                            | DaemonCommand::ToggleBluetooth
+                            | DaemonCommand::SetChargeLimit(_)
+                            | DaemonCommand::ResumeContainers => {
+                                // Hardware commands are carried out by the governor loop
+                                if cmd_tx.send(cmd).await.is_err() {
+                                    break;
+                                }
+                            }
+                            DaemonCommand::GetRyzenInfo => {
+                                let info = ryzen_info::read().await.map(Box::new);
+                                if resp_tx.send(DaemonResponse::RyzenInfo(info)).await.is_err() {
+                                    break;
+                                }
                             }
-                            DaemonCommand::ToggleBluetooth => {
-                                log::info!("Toggle Bluetooth (Not Implemented)");
+                            DaemonCommand::GetPlannedActions => {
+                                let planned = DaemonResponse::PlannedActions(plan::planned());
+                                if resp_tx.send(planned).await.is_err() {
+                                    break;
+                                }
+                            }
+                            DaemonCommand::GetTopConsumers(limit) => {
+                                let top = shared
+                                    .top_consumers
+                                    .lock()
+                                    .unwrap()
+                                    .iter()
+                                    .take(limit)
+                                    .cloned()
+                                    .collect();
+                                if resp_tx
+                                    .send(DaemonResponse::TopConsumers(top))
+                                    .await
+                                    .is_err()
+                                {
+                                    break;
+                                }
                             }
                         }
                     }
@@ -135,7 +437,7 @@ async fn start_ipc_server(
             });
 
             // Wait for either to finish (likely connection closed)
-            let _ = tokio::select! {
+            tokio::select! {
                 _ = writer_task => {},
                 _ = reader_task => {},
             };
@@ -163,148 +465,42 @@ async fn main() -> Result<()> {
         NitroConfig::load().expect("Failed to load configuration")
     });
 
+    if config.dry_run || std::env::args().any(|arg| arg == "--dry-run") {
+        log::info!("Dry run: hardware changes are only logged and recorded");
+        plan::enable_dry_run();
+    }
+
     // Graceful Exit Handler
     let config_clone = config.clone();
+    // The handler runs on its own thread; drive the async actuator from there
+    let runtime = tokio::runtime::Handle::current();
     ctrlc::set_handler(move || {
         log::info!("Exiting... Resetting to Pro Mode.");
         let mut actuator = actuator::Actuator::new(config_clone.clone());
-        actuator.apply_profile(&Profile::Pro, false); // Force apply Pro mode (unplugged logic to ensure it runs)
+        // Force apply Pro mode (unplugged logic to ensure it runs)
+        if let Err(e) = runtime.block_on(actuator.apply_profile(&Profile::Pro, false, None)) {
+            log::error!("Failed to reset power limits: {:#}", e);
+        }
         std::process::exit(0);
     })?;
 
     // Shared State
-    let shared_profile = Arc::new(Mutex::new(Profile::Eco));
+    let shared = Arc::new(Shared::default());
 
     // Initial state
-    let initial_state = PowerState {
-        battery_watts: 0.0,
-        cpu_watts: 0.0,
-        battery_percent: 0,
-        cpu_load: 0.0,
-        profile: Profile::Eco,
-        wifi_on: false,
-        bluetooth_on: false,
-        is_plugged_in: false,
-    };
+    let initial_state = PowerState::default();
 
     let (tx, rx) = watch::channel(initial_state);
+    let (cmd_tx, cmd_rx) = mpsc::channel::<DaemonCommand>(10);
 
     // Spawn IPC Server
-    let profile_for_server = shared_profile.clone();
+    let shared_for_server = shared.clone();
     tokio::spawn(async move {
-        if let Err(e) = start_ipc_server(rx, profile_for_server).await {
+        if let Err(e) = start_ipc_server(rx, cmd_tx, shared_for_server).await {
             log::error!("IPC Server Error: {}", e);
         }
     });
 
     // Run Sensor Loop
-    run_loop(tx, shared_profile, config).await
-}
-
-fn read_watts() -> f32 {
-    let bat1_power = "/sys/class/power_supply/BAT1/power_now";
-    let bat0_power = "/sys/class/power_supply/BAT0/power_now";
-
-    if let Ok(watts) = read_power_file(bat1_power) {
-        return watts;
-    }
-
-    if let Ok(watts) = read_power_file(bat0_power) {
-        return watts;
-    }
-
-    // Fallback: voltage_now * current_now
-    // Try BAT1 then BAT0 for these as well
-    if let Some(watts) = calculate_watts_from_voltage_current("BAT1") {
-        return watts;
-    }
-    if let Some(watts) = calculate_watts_from_voltage_current("BAT0") {
-        return watts;
-    }
-
-    0.0
-}
-
-fn read_power_file(path: &str) -> Result<f32> {
-    let content = fs::read_to_string(path)?;
-    let micro_watts: f32 = content.trim().parse()?;
-    Ok(micro_watts / 1_000_000.0)
-}
-
-fn calculate_watts_from_voltage_current(bat: &str) -> Option<f32> {
-    let voltage_path = format!("/sys/class/power_supply/{}/voltage_now", bat);
-    let current_path = format!("/sys/class/power_supply/{}/current_now", bat);
-
-    let voltage_str = fs::read_to_string(voltage_path).ok()?;
-    let current_str = fs::read_to_string(current_path).ok()?;
-
-    let voltage: f32 = voltage_str.trim().parse().ok()?;
-    let current: f32 = current_str.trim().parse().ok()?;
-
-    // voltage (uV) * current (uA) = pW (picowatts)
-    // pW / 10^12 = W
-    Some((voltage * current) / 1_000_000_000_000.0)
-}
-
-fn read_cpu_load() -> f32 {
-    match sys_info::loadavg() {
-        Ok(load) => load.one as f32, // Using 1-minute load average as a proxy for "current" load
-        Err(_) => 0.0,
-    }
-}
-
-fn read_cpu_watts() -> f32 {
-    // Run ryzenadj -i
-    if let Ok(output) = Command::new("ryzenadj").arg("-i").output() {
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        // Regex to find "PPT LIMIT FAST" or similar? No, user said "PPT VALUE FAST"
-        // Actually usually it's "PPT VALUE FAST" or just "PPT VALUE".
-        // Let's look for the table output.
-        // Example output:
-        // | PPT LIMIT FAST | 35.000 |
-        // | PPT VALUE FAST | 12.345 |
-        // We want the value.
-        let re = Regex::new(r"PPT VALUE FAST\s*\|\s*([\d\.]+)").unwrap();
-        if let Some(caps) = re.captures(&stdout) {
-            if let Some(val_str) = caps.get(1) {
-                if let Ok(val) = val_str.as_str().parse::<f32>() {
-                    return val;
-                }
-            }
-        }
-    }
-    0.0
-}
-
-// Helpers for other fields to make the struct more realistic
-fn read_battery_percent() -> u8 {
-    let paths = [
-        "/sys/class/power_supply/BAT1/capacity",
-        "/sys/class/power_supply/BAT0/capacity",
-    ];
-    for path in paths {
-        if let Ok(content) = fs::read_to_string(path) {
-            if let Ok(val) = content.trim().parse() {
-                return val;
-            }
-        }
-    }
-    0
-}
-
-fn read_is_plugged_in() -> bool {
-    let paths = [
-        "/sys/class/power_supply/AC/online",
-        "/sys/class/power_supply/ACAD/online",
-        "/sys/class/power_supply/ADP0/online",
-        "/sys/class/power_supply/ADP1/online",
-    ];
-    for path in paths {
-        if let Ok(content) = fs::read_to_string(path) {
-            if content.trim() == "1" {
-                return true;
-            }
-        }
-    }
-    false
+    run_loop(tx, cmd_rx, shared, config).await
 }
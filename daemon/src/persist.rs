@@ -0,0 +1,60 @@
+use crate::containers::PausedContainer;
+use crate::plan;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Daemon state that has to survive a reboot.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersistedState {
+    /// Charge limit set through `DaemonCommand::SetChargeLimit`, overriding
+    /// the profile's `charge_limit`.
+    #[serde(default)]
+    pub charge_limit: Option<u8>,
+    /// Containers Nitro paused, so a restart doesn't leave them frozen.
+    #[serde(default)]
+    pub paused_containers: Vec<PausedContainer>,
+}
+
+/// Loads and saves `PersistedState` as JSON.
+#[derive(Clone)]
+pub struct StateStore {
+    path: PathBuf,
+}
+
+impl StateStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    /// Missing or unreadable state is not an error: start from defaults.
+    pub fn load(&self) -> PersistedState {
+        match fs::read_to_string(&self.path) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                log::warn!("Ignoring corrupt state file {}: {}", self.path.display(), e);
+                PersistedState::default()
+            }),
+            Err(_) => PersistedState::default(),
+        }
+    }
+
+    pub fn save(&self, state: &PersistedState) -> Result<()> {
+        // A dry run must leave the state of the real daemon alone
+        if !plan::allow(format!("save state to {}", self.path.display())) {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent().filter(|d| !d.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        // Write then rename, so a crash never leaves a half-written file
+        let tmp = self.path.with_extension("tmp");
+        fs::write(&tmp, serde_json::to_string_pretty(state)?)?;
+        fs::rename(&tmp, &self.path)?;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
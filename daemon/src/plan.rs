@@ -0,0 +1,70 @@
+use nitro_core::PlannedAction;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tokio::process::Command;
+
+/// Distinct actions kept for `GetPlannedActions`; the oldest are dropped.
+const MAX_PLANNED: usize = 100;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+static PLANNED: Mutex<Vec<PlannedAction>> = Mutex::new(Vec::new());
+
+/// Turns every hardware write and command into a logged, recorded no-op.
+pub fn enable_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+/// Gate in front of every action that touches the hardware. Returns true if
+/// the caller should go ahead; in dry-run mode the action is recorded instead.
+pub fn allow(action: impl Into<String>) -> bool {
+    if !is_dry_run() {
+        return true;
+    }
+    let action = action.into();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+
+    let mut planned = PLANNED.lock().unwrap();
+    // Nothing is actually written, so the governor plans the same write every
+    // tick. Count repeats instead of flooding the list and the log.
+    if let Some(existing) = planned.iter_mut().find(|p| p.action == action) {
+        existing.count += 1;
+        existing.last_at = now;
+        return false;
+    }
+    log::info!("[dry-run] Would {}", action);
+    planned.push(PlannedAction {
+        action,
+        count: 1,
+        last_at: now,
+    });
+    if planned.len() > MAX_PLANNED {
+        planned.remove(0);
+    }
+    false
+}
+
+/// `allow` for running a command, described by its program and arguments.
+pub fn allow_command(cmd: &Command) -> bool {
+    if !is_dry_run() {
+        return true;
+    }
+    let cmd = cmd.as_std();
+    let mut line = cmd.get_program().to_string_lossy().into_owned();
+    for arg in cmd.get_args() {
+        line.push(' ');
+        line.push_str(&arg.to_string_lossy());
+    }
+    allow(format!("run {}", line))
+}
+
+pub fn planned() -> Vec<PlannedAction> {
+    PLANNED.lock().unwrap().clone()
+}
@@ -0,0 +1,67 @@
+use crate::attr::{read_string, write_if_changed};
+use crate::config::ProfileConfig;
+use crate::log_once::LogOnce;
+use anyhow::{bail, Result};
+use std::path::PathBuf;
+
+const AMD_PSTATE_STATUS: &str = "sys/devices/system/cpu/amd_pstate/status";
+const PLATFORM_PROFILE: &str = "sys/firmware/acpi/platform_profile";
+const PLATFORM_PROFILE_CHOICES: &str = "sys/firmware/acpi/platform_profile_choices";
+
+/// Modes amd_pstate accepts in `status` (besides "disable", which we never set).
+const AMD_PSTATE_MODES: [&str; 3] = ["active", "passive", "guided"];
+
+/// Sets the amd_pstate driver mode and the ACPI platform profile.
+///
+/// Firmware (and Fn-key handlers) flip `platform_profile` on its own, so like
+/// the TDP limits this is re-checked every tick.
+pub struct PlatformTuner {
+    root: PathBuf,
+    errors: LogOnce,
+}
+
+impl PlatformTuner {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            errors: LogOnce::default(),
+        }
+    }
+
+    pub fn apply(&mut self, profile: &ProfileConfig) {
+        let result = self.try_apply(profile);
+        self.errors.report("apply platform settings", result);
+    }
+
+    fn try_apply(&self, profile: &ProfileConfig) -> Result<()> {
+        if let Some(mode) = &profile.amd_pstate {
+            if !AMD_PSTATE_MODES.contains(&mode.as_str()) {
+                bail!("amd_pstate {:?} is not one of {:?}", mode, AMD_PSTATE_MODES);
+            }
+            let status = self.root.join(AMD_PSTATE_STATUS);
+            if !status.exists() {
+                bail!("amd_pstate is not loaded");
+            }
+            write_if_changed(&status, mode)?;
+        }
+
+        if let Some(wanted) = &profile.platform_profile {
+            let Some(choices) = read_string(&self.root.join(PLATFORM_PROFILE_CHOICES)) else {
+                bail!("No ACPI platform_profile support");
+            };
+            if !choices.split_whitespace().any(|c| c == wanted) {
+                bail!("platform_profile {:?} is not one of {}", wanted, choices);
+            }
+            write_if_changed(&self.root.join(PLATFORM_PROFILE), wanted)?;
+        }
+        Ok(())
+    }
+
+    pub fn amd_pstate(&self) -> Option<String> {
+        read_string(&self.root.join(AMD_PSTATE_STATUS))
+    }
+
+    pub fn platform_profile(&self) -> Option<String> {
+        read_string(&self.root.join(PLATFORM_PROFILE))
+    }
+}
@@ -0,0 +1,154 @@
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc;
+
+/// Anything shorter is clock jitter, not a suspend.
+const MIN_SUSPEND: Duration = Duration::from_secs(1);
+
+/// Detects suspend/resume cycles.
+///
+/// CLOCK_BOOTTIME keeps counting while suspended, CLOCK_MONOTONIC does
+/// not, so the gap between them grows by exactly the time spent asleep.
+pub struct ResumeDetector {
+    last_gap: Duration,
+}
+
+impl ResumeDetector {
+    pub fn new() -> Self {
+        Self {
+            last_gap: suspended_total(),
+        }
+    }
+
+    /// Returns how long the machine slept if it was suspended since the last call.
+    pub fn check(&mut self) -> Option<Duration> {
+        let gap = suspended_total();
+        let slept = gap.saturating_sub(self.last_gap);
+        self.last_gap = gap;
+        (slept >= MIN_SUSPEND).then_some(slept)
+    }
+}
+
+/// Wakes the governor right after a resume instead of on its next tick,
+/// which CLOCK_MONOTONIC timers only reach up to a full period late.
+///
+/// The kernel cancels CLOCK_REALTIME timers armed with
+/// `TFD_TIMER_CANCEL_ON_SET` whenever the wall clock is set, and resuming
+/// sets it. Manual clock changes wake the governor too; `ResumeDetector`
+/// tells them apart. Without timerfd this logs and returns.
+pub fn spawn_watcher(tx: mpsc::Sender<()>) {
+    tokio::spawn(async move {
+        let timer = match open_timer() {
+            Ok(timer) => timer,
+            Err(e) => {
+                log::warn!(
+                    "Clock change timer unavailable, resume is noticed on the next tick: {}",
+                    e
+                );
+                return;
+            }
+        };
+        loop {
+            if let Err(e) = wait_clock_set(&timer).await {
+                log::error!("Clock change timer failed: {}", e);
+                return;
+            }
+            if tx.send(()).await.is_err() {
+                return;
+            }
+        }
+    });
+}
+
+fn open_timer() -> io::Result<AsyncFd<OwnedFd>> {
+    // SAFETY: plain timerfd_create(2); the fd is owned by OwnedFd right away
+    let fd = unsafe {
+        libc::timerfd_create(libc::CLOCK_REALTIME, libc::TFD_NONBLOCK | libc::TFD_CLOEXEC)
+    };
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // SAFETY: fd was just created and is not owned by anything else
+    let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+    arm(&fd)?;
+    AsyncFd::new(fd)
+}
+
+/// Arms the timer for a time that never comes; only a clock change fires it.
+fn arm(fd: &OwnedFd) -> io::Result<()> {
+    let spec = libc::itimerspec {
+        it_interval: libc::timespec {
+            tv_sec: 0,
+            tv_nsec: 0,
+        },
+        it_value: libc::timespec {
+            tv_sec: libc::time_t::from(i32::MAX),
+            tv_nsec: 0,
+        },
+    };
+    // SAFETY: spec is a valid itimerspec; the old value isn't requested
+    let ret = unsafe {
+        libc::timerfd_settime(
+            fd.as_raw_fd(),
+            libc::TFD_TIMER_ABSTIME | libc::TFD_TIMER_CANCEL_ON_SET,
+            &spec,
+            std::ptr::null_mut(),
+        )
+    };
+    if ret < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// Waits until the wall clock is set, then re-arms the timer.
+async fn wait_clock_set(timer: &AsyncFd<OwnedFd>) -> io::Result<()> {
+    loop {
+        let mut guard = timer.readable().await?;
+        let result = guard.try_io(|fd| {
+            let mut expirations = 0u64;
+            // SAFETY: expirations is a valid, writable u64
+            let n = unsafe {
+                libc::read(
+                    fd.as_raw_fd(),
+                    &mut expirations as *mut u64 as *mut libc::c_void,
+                    std::mem::size_of::<u64>(),
+                )
+            };
+            if n < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(())
+            }
+        });
+        match result {
+            // A cancelled timer stays cancelled until it is set again
+            Ok(Err(e)) if e.raw_os_error() == Some(libc::ECANCELED) => {
+                return arm(timer.get_ref());
+            }
+            Ok(Err(e)) => return Err(e),
+            // Expired, which it only does in 2038; arm it again
+            Ok(Ok(())) => arm(timer.get_ref())?,
+            Err(_would_block) => continue,
+        }
+    }
+}
+
+/// Total time spent suspended since boot.
+fn suspended_total() -> Duration {
+    read_clock(libc::CLOCK_BOOTTIME).saturating_sub(read_clock(libc::CLOCK_MONOTONIC))
+}
+
+fn read_clock(clock: libc::clockid_t) -> Duration {
+    let mut ts = libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    // SAFETY: ts is a valid, writable timespec
+    if unsafe { libc::clock_gettime(clock, &mut ts) } != 0 {
+        return Duration::ZERO;
+    }
+    Duration::new(ts.tv_sec as u64, ts.tv_nsec as u32)
+}
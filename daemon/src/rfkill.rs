@@ -0,0 +1,48 @@
+use crate::attr;
+use anyhow::{bail, Result};
+use nitro_core::RadioState;
+use std::fs;
+use std::path::PathBuf;
+
+/// Soft-blocks and unblocks radios through `/sys/class/rfkill`.
+pub struct RfkillSwitch {
+    root: PathBuf,
+}
+
+impl RfkillSwitch {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Flips every switch of `kind` ("wlan", "bluetooth") based on its current state.
+    pub fn toggle(&self, kind: &str, current: &RadioState) -> Result<()> {
+        let block = match current {
+            RadioState::On => true,
+            RadioState::SoftBlocked => false,
+            RadioState::HardBlocked => bail!("{} is blocked by a hardware switch", kind),
+            RadioState::Absent => bail!("No {} rfkill device found", kind),
+        };
+        self.set_soft_blocked(kind, block)
+    }
+
+    fn set_soft_blocked(&self, kind: &str, blocked: bool) -> Result<()> {
+        let class_dir = self.root.join("sys/class/rfkill");
+        let value = if blocked { "1" } else { "0" };
+
+        for entry in fs::read_dir(&class_dir)?.flatten() {
+            let dir = entry.path();
+            let is_kind = attr::read_string(&dir.join("type")).as_deref() == Some(kind);
+            if !is_kind {
+                continue;
+            }
+            log::info!(
+                "{} {} ({})",
+                if blocked { "Blocking" } else { "Unblocking" },
+                kind,
+                dir.display()
+            );
+            attr::write(&dir.join("soft"), value)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use crate::exec;
+use nitro_core::{LimitValue, RyzenInfo};
+use std::time::Instant;
+use tokio::process::Command;
+use tokio::task::JoinHandle;
+
+/// Runs `read` on its own task, so a `ryzenadj -i` that is slow to finish
+/// (or to be killed) never holds up the caller.
+#[derive(Default)]
+pub struct BackgroundReader {
+    running: Option<(Instant, JoinHandle<Option<RyzenInfo>>)>,
+    last: Option<(Instant, RyzenInfo)>,
+}
+
+impl BackgroundReader {
+    /// The newest finished reading, with the time it was started. Starts
+    /// the next read unless one is still running.
+    pub async fn latest(&mut self) -> Option<(Instant, RyzenInfo)> {
+        if let Some((started, read)) = self.running.take_if(|(_, read)| read.is_finished()) {
+            self.last = read.await.ok().flatten().map(|info| (started, info));
+        }
+        if self.running.is_none() {
+            self.running = Some((Instant::now(), tokio::spawn(read())));
+        }
+        self.last.clone()
+    }
+}
+
+/// Runs `ryzenadj -i` and parses its table. `None` if ryzenadj can't run.
+pub async fn read() -> Option<RyzenInfo> {
+    let output = match exec::output(Command::new("ryzenadj").arg("-i"), exec::TIMEOUT).await {
+        Ok(output) => output,
+        Err(e) => {
+            log::error!("ryzenadj -i: {:#}", e);
+            return None;
+        }
+    };
+    if !output.status.success() {
+        log::error!(
+            "ryzenadj -i failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        return None;
+    }
+    Some(parse(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Parses the output of `ryzenadj -i`.
+///
+/// Handles both the old two-column table (`| Name | Value |`) and the newer
+/// one with a Parameter column, and any header lines before it. Rows reading
+/// `nan` are skipped, unknown rows end up in `RyzenInfo::other`.
+pub fn parse(output: &str) -> RyzenInfo {
+    let mut info = RyzenInfo::default();
+
+    for line in output.lines() {
+        let line = line.trim();
+
+        if let Some((key, value)) = line.split_once(':') {
+            if !line.starts_with('|') {
+                match key.trim() {
+                    "CPU Family" => info.cpu_family = Some(value.trim().to_string()),
+                    "PM Table Version" => info.pm_table_version = Some(value.trim().to_string()),
+                    _ => {}
+                }
+                continue;
+            }
+        }
+
+        let cells: Vec<&str> = line
+            .trim_matches('|')
+            .split('|')
+            .map(|cell| cell.trim())
+            .collect();
+        let [name, value, ..] = cells[..] else {
+            continue;
+        };
+        let Ok(value) = value.parse::<f32>() else {
+            // Header, separator or "nan"
+            continue;
+        };
+        if value.is_nan() {
+            continue;
+        }
+        set_row(&mut info, name, value);
+    }
+
+    info
+}
+
+fn set_row(info: &mut RyzenInfo, name: &str, value: f32) {
+    let words: Vec<String> = name.split_whitespace().map(str::to_uppercase).collect();
+
+    // "PPT LIMIT FAST" -> ("PPT FAST", limit), "STAPM VALUE" -> ("STAPM", value)
+    let kind = words.iter().position(|w| w == "LIMIT" || w == "VALUE");
+    if let Some(pos) = kind {
+        let is_limit = words[pos] == "LIMIT";
+        let mut key = words.clone();
+        key.remove(pos);
+        let pair = match key.join(" ").as_str() {
+            "STAPM" => Some(&mut info.stapm),
+            "PPT FAST" => Some(&mut info.ppt_fast),
+            "PPT SLOW" => Some(&mut info.ppt_slow),
+            "PPT APU" => Some(&mut info.ppt_apu),
+            "TDC VDD" => Some(&mut info.tdc_vdd),
+            "TDC SOC" => Some(&mut info.tdc_soc),
+            "EDC VDD" => Some(&mut info.edc_vdd),
+            "EDC SOC" => Some(&mut info.edc_soc),
+            "THM CORE" => Some(&mut info.thm_core),
+            "STT APU" => Some(&mut info.stt_apu),
+            "STT DGPU" => Some(&mut info.stt_dgpu),
+            _ => None,
+        };
+        if let Some(pair) = pair {
+            set_pair(pair, is_limit, value);
+            return;
+        }
+    }
+
+    let field = match words.join(" ").as_str() {
+        "STAPMTIMECONST" => &mut info.stapm_time_const,
+        "SLOWPPTTIMECONST" => &mut info.slow_ppt_time_const,
+        "CCLK BOOST SETPOINT" => &mut info.cclk_setpoint,
+        "CCLK BUSY VALUE" => &mut info.cclk_busy,
+        _ => {
+            info.other.insert(name.to_string(), value);
+            return;
+        }
+    };
+    *field = Some(value);
+}
+
+fn set_pair(pair: &mut LimitValue, is_limit: bool, value: f32) {
+    if is_limit {
+        pair.limit = Some(value);
+    } else {
+        pair.value = Some(value);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REMBRANDT: &str = "\
+CPU Family: Rembrandt
+SMU BIOS Interface Version: 18
+Version: v0.13.0
+PM Table Version: 450005
+|        Name         |   Value   |     Parameter      |
+|---------------------|-----------|--------------------|
+| STAPM LIMIT         |     8.000 | stapm-limit        |
+| STAPM VALUE         |     6.235 |                    |
+| PPT LIMIT FAST      |    15.000 | fast-limit         |
+| PPT VALUE FAST      |    10.447 |                    |
+| PPT LIMIT SLOW      |     8.000 | slow-limit         |
+| PPT VALUE SLOW      |     7.031 |                    |
+| StapmTimeConst      |   200.000 | stapm-time         |
+| SlowPPTTimeConst    |     5.000 | slow-time          |
+| PPT LIMIT APU       |    54.000 | apu-slow-limit     |
+| PPT VALUE APU       |     7.031 |                    |
+| TDC LIMIT VDD       |    60.000 | vrm-current        |
+| TDC VALUE VDD       |     4.234 |                    |
+| TDC LIMIT SOC       |    10.000 | vrmsoc-current     |
+| TDC VALUE SOC       |     1.623 |                    |
+| EDC LIMIT VDD       |    90.000 | vrmmax-current     |
+| EDC VALUE VDD       |    40.234 |                    |
+| EDC LIMIT SOC       |    15.000 | vrmsocmax-current  |
+| EDC VALUE SOC       |     5.000 |                    |
+| THM LIMIT CORE      |    85.000 | tctl-temp          |
+| THM VALUE CORE      |    45.234 |                    |
+| STT LIMIT APU       |     0.000 | apu-skin-temp      |
+| STT VALUE APU       |     0.000 |                    |
+| STT LIMIT dGPU      |     0.000 | dgpu-skin-temp     |
+| STT VALUE dGPU      |       nan |                    |
+| CCLK Boost SETPOINT |    95.000 | power-saving /     |
+| CCLK BUSY VALUE     |    30.234 | max-performance    |
+";
+
+    const OLD_TWO_COLUMN: &str = "\
+CPU Family: Cezanne
+|        Name         |   Value   |
+|---------------------|-----------|
+| STAPM LIMIT         |    25.000 |
+| PPT VALUE FAST      |    12.345 |
+| GFX CLK             |   400.000 |
+";
+
+    #[test]
+    fn parses_current_table() {
+        let info = parse(REMBRANDT);
+        assert_eq!(info.cpu_family.as_deref(), Some("Rembrandt"));
+        assert_eq!(info.pm_table_version.as_deref(), Some("450005"));
+        assert_eq!(info.stapm.limit, Some(8.0));
+        assert_eq!(info.stapm.value, Some(6.235));
+        assert_eq!(info.ppt_fast.value, Some(10.447));
+        assert_eq!(info.ppt_slow.limit, Some(8.0));
+        assert_eq!(info.edc_soc.limit, Some(15.0));
+        assert_eq!(info.thm_core.limit, Some(85.0));
+        assert_eq!(info.stapm_time_const, Some(200.0));
+        assert_eq!(info.cclk_busy, Some(30.234));
+        assert_eq!(info.stt_dgpu.value, None);
+        assert!(info.other.is_empty());
+    }
+
+    #[test]
+    fn parses_two_column_table_and_keeps_unknown_rows() {
+        let info = parse(OLD_TWO_COLUMN);
+        assert_eq!(info.cpu_family.as_deref(), Some("Cezanne"));
+        assert_eq!(info.stapm.limit, Some(25.0));
+        assert_eq!(info.ppt_fast.value, Some(12.345));
+        assert_eq!(info.other.get("GFX CLK"), Some(&400.0));
+    }
+}
@@ -0,0 +1,85 @@
+/// Jiffy counters for one `cpu*` line of `/proc/stat`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CpuTimes {
+    pub busy: u64,
+    pub total: u64,
+}
+
+impl CpuTimes {
+    /// Parses `cpu  user nice system idle iowait irq softirq steal ...`.
+    /// guest/guest_nice are already folded into user/nice by the kernel.
+    pub fn parse_line(line: &str) -> Option<Self> {
+        let mut fields = line.split_whitespace();
+        if !fields.next()?.starts_with("cpu") {
+            return None;
+        }
+        let values: Vec<u64> = fields
+            .take(8)
+            .map(|f| f.parse().ok())
+            .collect::<Option<_>>()?;
+        if values.len() < 4 {
+            return None;
+        }
+        let total: u64 = values.iter().sum();
+        let idle = values[3] + values.get(4).copied().unwrap_or(0);
+        Some(Self {
+            busy: total - idle,
+            total,
+        })
+    }
+
+    fn util_since(&self, prev: &CpuTimes) -> f32 {
+        let total = self.total.saturating_sub(prev.total);
+        if total == 0 {
+            return 0.0;
+        }
+        let busy = self.busy.saturating_sub(prev.busy);
+        (busy as f32 / total as f32 * 100.0).clamp(0.0, 100.0)
+    }
+}
+
+/// Busy percentages between the last two samples.
+#[derive(Debug, Clone, Default)]
+pub struct CpuUtil {
+    pub total: f32,
+    pub per_core: Vec<f32>,
+}
+
+/// Turns successive `/proc/stat` snapshots into utilization figures.
+#[derive(Default)]
+pub struct CpuUtilTracker {
+    prev: Option<(CpuTimes, Vec<CpuTimes>)>,
+}
+
+impl CpuUtilTracker {
+    /// `times` is the aggregate `cpu` line followed by `cpu0..cpuN`.
+    /// The first call only primes the tracker and reports zero.
+    pub fn update(&mut self, times: &[CpuTimes]) -> CpuUtil {
+        let Some((total, cores)) = times.split_first() else {
+            return CpuUtil::default();
+        };
+
+        let util = match &self.prev {
+            // Core count changed (hotplug): skip the per-core delta this round.
+            Some((prev_total, prev_cores)) => CpuUtil {
+                total: total.util_since(prev_total),
+                per_core: if prev_cores.len() == cores.len() {
+                    cores
+                        .iter()
+                        .zip(prev_cores)
+                        .map(|(now, prev)| now.util_since(prev))
+                        .collect()
+                } else {
+                    vec![0.0; cores.len()]
+                },
+            },
+            None => CpuUtil {
+                total: 0.0,
+                per_core: vec![0.0; cores.len()],
+            },
+        };
+
+        self.prev = Some((*total, cores.to_vec()));
+        util
+    }
+}
@@ -0,0 +1,51 @@
+use std::time::Instant;
+
+/// A cumulative package energy counter (powercap zone or hwmon channel).
+#[derive(Debug, Clone)]
+pub struct EnergyCounter {
+    /// Stable identifier, e.g. `intel-rapl:0` or `hwmon3/energy17`.
+    pub id: String,
+    pub energy_uj: u64,
+    /// Counter wraps back to 0 after this value (powercap `max_energy_range_uj`).
+    pub max_energy_uj: Option<u64>,
+}
+
+/// Computes average CPU package power from energy counter deltas.
+#[derive(Default)]
+pub struct CpuPowerMeter {
+    prev: Option<(Instant, Vec<EnergyCounter>)>,
+}
+
+impl CpuPowerMeter {
+    /// Returns the average power since the previous call, or `None` if there
+    /// are no counters on this machine or this is the first sample.
+    pub fn update(&mut self, counters: Vec<EnergyCounter>) -> Option<f32> {
+        if counters.is_empty() {
+            self.prev = None;
+            return None;
+        }
+
+        let now = Instant::now();
+        let watts = self.prev.as_ref().and_then(|(then, prev)| {
+            let secs = now.duration_since(*then).as_secs_f32();
+            if secs <= 0.0 {
+                return None;
+            }
+            let mut joules = 0.0;
+            for counter in &counters {
+                let old = prev.iter().find(|p| p.id == counter.id)?;
+                let delta = if counter.energy_uj >= old.energy_uj {
+                    counter.energy_uj - old.energy_uj
+                } else {
+                    // Wrapped around
+                    counter.max_energy_uj?.saturating_sub(old.energy_uj) + counter.energy_uj
+                };
+                joules += delta as f32 / 1_000_000.0;
+            }
+            Some(joules / secs)
+        });
+
+        self.prev = Some((now, counters));
+        watts
+    }
+}
@@ -0,0 +1,54 @@
+mod cpu;
+mod energy;
+mod pm_table;
+mod power_supply;
+mod process;
+mod rfkill;
+mod sysfs;
+
+use nitro_core::{GpuState, Temperature};
+
+pub use cpu::{CpuTimes, CpuUtilTracker};
+pub use energy::{CpuPowerMeter, EnergyCounter};
+pub use pm_table::PmTable;
+pub use power_supply::{PowerSupply, PowerSupplySummary, SupplyKind};
+pub use process::{ProcessSample, ProcessTracker};
+pub use rfkill::{radio_state, RfkillDevice};
+pub use sysfs::SysfsBackend;
+
+/// Source of raw hardware readings for the governor.
+///
+/// The daemon only ever talks to this trait, so it can be pointed at a
+/// fake sysfs tree (or a completely synthetic backend) instead of the
+/// real `/sys` of a Nitro 5.
+pub trait SensorBackend: Send {
+    /// Every device under `/sys/class/power_supply`, batteries and adapters alike.
+    fn power_supplies(&self) -> Vec<PowerSupply>;
+
+    /// `/proc/stat` counters: the aggregate `cpu` line first, then one per core.
+    fn cpu_times(&self) -> Vec<CpuTimes>;
+
+    /// 1-minute load average from `/proc/loadavg`.
+    fn load_average(&self) -> f32;
+
+    /// CPU package energy counters from powercap (`intel-rapl:*` package
+    /// zones, also used by AMD) or the `amd_energy`/`zenpower` hwmon drivers.
+    fn energy_counters(&self) -> Vec<EnergyCounter>;
+
+    /// k10temp/zenpower Tctl and Tccd, NVMe and GPU hwmon sensors, and ACPI thermal zones.
+    fn temperatures(&self) -> Vec<Temperature>;
+
+    /// CPU time of every process in `/proc`. Wakeup counters come from
+    /// `/proc/[pid]/sched`, which is costlier to read, so they are opt-in.
+    fn processes(&self, with_wakeups: bool) -> Vec<ProcessSample>;
+
+    /// Display-class PCI devices that are not the boot VGA device.
+    fn discrete_gpus(&self) -> Vec<GpuState>;
+
+    /// Radio kill switches under `/sys/class/rfkill`.
+    fn rfkill_devices(&self) -> Vec<RfkillDevice>;
+
+    /// SMU power management table from `/sys/kernel/ryzen_smu_drv`, if the
+    /// ryzen_smu module is loaded.
+    fn pm_table(&self) -> Option<PmTable>;
+}
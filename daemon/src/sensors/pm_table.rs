@@ -0,0 +1,142 @@
+use nitro_core::{LimitValue, RyzenInfo};
+
+/// Raw SMU power management table from the ryzen_smu kernel module.
+#[derive(Debug, Clone)]
+pub struct PmTable {
+    pub version: u32,
+    pub data: Vec<u8>,
+}
+
+/// Families whose pm_table layout we know, by table version.
+fn family(version: u32) -> Option<&'static str> {
+    match version {
+        0x400001..=0x400005 => Some("Cezanne"),
+        0x450004 | 0x450005 => Some("Rembrandt"),
+        0x4C0003..=0x4C0009 => Some("Phoenix"),
+        _ => None,
+    }
+}
+
+impl PmTable {
+    /// Decodes the table into the same shape `ryzenadj -i` produces.
+    /// Returns `None` for table versions we don't have a layout for.
+    ///
+    /// All three known families share the leading limit/value block
+    /// (STAPM, PPT, TDC, EDC, THM), which is all the governor needs.
+    /// Clock fields move around between BIOS releases and are not decoded.
+    pub fn decode(&self) -> Option<RyzenInfo> {
+        let family = family(self.version)?;
+        let pair = |offset: usize| LimitValue {
+            limit: self.read_f32(offset),
+            value: self.read_f32(offset + 4),
+        };
+
+        Some(RyzenInfo {
+            cpu_family: Some(family.to_string()),
+            pm_table_version: Some(format!("{:x}", self.version)),
+            stapm: pair(0x00),
+            ppt_fast: pair(0x08),
+            ppt_slow: pair(0x10),
+            ppt_apu: pair(0x18),
+            tdc_vdd: pair(0x20),
+            tdc_soc: pair(0x28),
+            edc_vdd: pair(0x30),
+            edc_soc: pair(0x38),
+            thm_core: pair(0x40),
+            ..RyzenInfo::default()
+        })
+    }
+
+    fn read_f32(&self, offset: usize) -> Option<f32> {
+        let bytes = self.data.get(offset..offset + 4)?;
+        let value = f32::from_le_bytes(bytes.try_into().ok()?);
+        value.is_finite().then_some(value)
+    }
+}
+
+/// `pm_table_version` is a raw little-endian u32; some module versions
+/// print it as hex text instead.
+pub fn parse_version(raw: &[u8]) -> Option<u32> {
+    if let Ok(bytes) = <[u8; 4]>::try_from(raw) {
+        return Some(u32::from_le_bytes(bytes));
+    }
+    let text = std::str::from_utf8(raw).ok()?.trim();
+    u32::from_str_radix(text.trim_start_matches("0x"), 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Leading limit/value block of a Rembrandt table as ryzen_smu exposes it,
+    /// followed by the start of the (undecoded) clock section.
+    fn rembrandt_bytes() -> Vec<u8> {
+        let pairs: [(f32, f32); 10] = [
+            (8.0, 6.235),   // STAPM
+            (15.0, 10.447), // PPT fast
+            (8.0, 7.031),   // PPT slow
+            (54.0, 7.031),  // PPT APU
+            (60.0, 4.234),  // TDC VDD
+            (10.0, 1.623),  // TDC SOC
+            (90.0, 40.234), // EDC VDD
+            (15.0, 5.0),    // EDC SOC
+            (85.0, 45.234), // THM core
+            (f32::NAN, 400.0),
+        ];
+        pairs
+            .iter()
+            .flat_map(|(limit, value)| [limit.to_le_bytes(), value.to_le_bytes()])
+            .flatten()
+            .collect()
+    }
+
+    #[test]
+    fn decodes_known_version() {
+        let table = PmTable {
+            version: 0x450005,
+            data: rembrandt_bytes(),
+        };
+        let info = table.decode().unwrap();
+        assert_eq!(info.cpu_family.as_deref(), Some("Rembrandt"));
+        assert_eq!(info.pm_table_version.as_deref(), Some("450005"));
+        assert_eq!(info.stapm.limit, Some(8.0));
+        assert_eq!(info.ppt_fast.value, Some(10.447));
+        assert_eq!(info.ppt_slow.limit, Some(8.0));
+        assert_eq!(info.tdc_soc.value, Some(1.623));
+        assert_eq!(info.edc_vdd.limit, Some(90.0));
+        assert_eq!(info.thm_core.value, Some(45.234));
+        assert_eq!(info.cclk_busy, None);
+    }
+
+    #[test]
+    fn short_table_leaves_missing_fields_empty() {
+        let mut data = rembrandt_bytes();
+        data.truncate(0x44);
+        let info = PmTable {
+            version: 0x4C0006,
+            data,
+        }
+        .decode()
+        .unwrap();
+        assert_eq!(info.cpu_family.as_deref(), Some("Phoenix"));
+        assert_eq!(info.thm_core.limit, Some(85.0));
+        assert_eq!(info.thm_core.value, None);
+    }
+
+    #[test]
+    fn rejects_unknown_version() {
+        let table = PmTable {
+            version: 0x5D0008,
+            data: rembrandt_bytes(),
+        };
+        assert_eq!(table.decode(), None);
+    }
+
+    #[test]
+    fn parses_raw_and_text_versions() {
+        assert_eq!(parse_version(&0x450005u32.to_le_bytes()), Some(0x450005));
+        assert_eq!(parse_version(b"0x4c0006\n"), Some(0x4C0006));
+        assert_eq!(parse_version(b"400005\n"), Some(0x400005));
+        assert_eq!(parse_version(b"zen"), None);
+    }
+}
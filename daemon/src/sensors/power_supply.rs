@@ -0,0 +1,267 @@
+use nitro_core::{BatteryInfo, BatteryStatus};
+
+/// What a `/sys/class/power_supply/*` entry is, from its `type` attribute.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SupplyKind {
+    Battery,
+    Mains,
+    Usb,
+    Other,
+}
+
+impl SupplyKind {
+    pub fn from_type(value: &str) -> Self {
+        match value {
+            "Battery" => SupplyKind::Battery,
+            "Mains" => SupplyKind::Mains,
+            // USB, USB_C, USB_PD, ... (ucsi-source-psy-* on Type-C chargers)
+            v if v.starts_with("USB") => SupplyKind::Usb,
+            _ => SupplyKind::Other,
+        }
+    }
+}
+
+/// Raw snapshot of a single power_supply device.
+/// Units are the kernel's: µW, µV, µA, µWh.
+#[derive(Debug, Clone)]
+pub struct PowerSupply {
+    pub name: String,
+    pub kind: SupplyKind,
+    /// `scope` is "Device" for peripherals (mice, headsets) that must not be
+    /// mistaken for the laptop's own battery or charger.
+    pub is_system: bool,
+    pub online: Option<bool>,
+    pub capacity: Option<u8>,
+    pub power_now: Option<f32>,
+    pub voltage_now: Option<f32>,
+    pub current_now: Option<f32>,
+    pub energy_now: Option<f32>,
+    pub energy_full: Option<f32>,
+    pub energy_full_design: Option<f32>,
+    pub status: Option<String>,
+    pub cycle_count: Option<u32>,
+    pub technology: Option<String>,
+    pub manufacturer: Option<String>,
+    pub model_name: Option<String>,
+}
+
+impl PowerSupply {
+    pub fn is_system_battery(&self) -> bool {
+        self.kind == SupplyKind::Battery && self.is_system
+    }
+
+    pub fn is_adapter(&self) -> bool {
+        matches!(self.kind, SupplyKind::Mains | SupplyKind::Usb) && self.is_system
+    }
+
+    /// Charge/discharge rate in Watts, from `power_now` or `voltage_now * current_now`.
+    pub fn watts(&self) -> Option<f32> {
+        if let Some(micro_watts) = self.power_now {
+            return Some(micro_watts / 1_000_000.0);
+        }
+        // voltage (uV) * current (uA) = pW (picowatts)
+        // pW / 10^12 = W
+        let (voltage, current) = (self.voltage_now?, self.current_now?);
+        Some((voltage * current).abs() / 1_000_000_000_000.0)
+    }
+
+    pub fn battery_info(&self) -> BatteryInfo {
+        let wh = |micro_wh: Option<f32>| micro_wh.map(|v| v / 1_000_000.0);
+        BatteryInfo {
+            name: self.name.clone(),
+            status: self
+                .status
+                .as_deref()
+                .map(BatteryStatus::from_sysfs)
+                .unwrap_or_default(),
+            energy_now_wh: wh(self.energy_now),
+            energy_full_wh: wh(self.energy_full),
+            energy_full_design_wh: wh(self.energy_full_design),
+            cycle_count: self.cycle_count,
+            technology: self.technology.clone(),
+            manufacturer: self.manufacturer.clone(),
+            model_name: self.model_name.clone(),
+        }
+    }
+}
+
+/// The aggregated view the governor cares about.
+#[derive(Debug, Clone, Default)]
+pub struct PowerSupplySummary {
+    pub battery_watts: f32,
+    pub battery_percent: u8,
+    pub is_plugged_in: bool,
+    pub batteries: Vec<BatteryInfo>,
+}
+
+impl PowerSupplySummary {
+    pub fn from_supplies(supplies: &[PowerSupply]) -> Self {
+        let batteries: Vec<&PowerSupply> =
+            supplies.iter().filter(|s| s.is_system_battery()).collect();
+
+        let battery_watts = batteries.iter().filter_map(|b| b.watts()).sum();
+
+        // Weight by capacity when every battery reports energy, otherwise
+        // fall back to the plain average of `capacity`.
+        let energy: Option<(f32, f32)> = batteries
+            .iter()
+            .map(|b| Some((b.energy_now?, b.energy_full?)))
+            .try_fold((0.0, 0.0), |(now, full), e: Option<(f32, f32)>| {
+                e.map(|(n, f)| (now + n, full + f))
+            });
+        let battery_percent = match energy {
+            Some((now, full)) if full > 0.0 => (now / full * 100.0).round().clamp(0.0, 100.0) as u8,
+            _ => {
+                let caps: Vec<u32> = batteries
+                    .iter()
+                    .filter_map(|b| b.capacity.map(u32::from))
+                    .collect();
+                if caps.is_empty() {
+                    0
+                } else {
+                    (caps.iter().sum::<u32>() / caps.len() as u32) as u8
+                }
+            }
+        };
+
+        let is_plugged_in = supplies
+            .iter()
+            .any(|s| s.is_adapter() && s.online == Some(true));
+
+        Self {
+            battery_watts,
+            battery_percent,
+            is_plugged_in,
+            batteries: batteries.iter().map(|b| b.battery_info()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fake_sysfs::FakeSysfs;
+    use crate::sensors::{SensorBackend, SysfsBackend};
+
+    fn read_summary(sysfs: &FakeSysfs) -> PowerSupplySummary {
+        let supplies = SysfsBackend::new(sysfs.root()).power_supplies();
+        PowerSupplySummary::from_supplies(&supplies)
+    }
+
+    #[test]
+    fn two_batteries_are_weighted_by_energy() {
+        let sysfs = FakeSysfs::new("two-batteries");
+        sysfs
+            .supply(
+                "BAT0",
+                &[
+                    ("type", "Battery"),
+                    ("status", "Discharging"),
+                    ("capacity", "90"),
+                    ("power_now", "6000000"),
+                    ("energy_now", "45000000"),
+                    ("energy_full", "50000000"),
+                ],
+            )
+            .supply(
+                "BAT1",
+                &[
+                    ("type", "Battery"),
+                    ("status", "Discharging"),
+                    ("capacity", "10"),
+                    ("power_now", "2000000"),
+                    ("energy_now", "2000000"),
+                    ("energy_full", "20000000"),
+                ],
+            );
+
+        let summary = read_summary(&sysfs);
+        assert_eq!(summary.batteries.len(), 2);
+        assert_eq!(summary.battery_watts, 8.0);
+        // 47 Wh of 70 Wh, not the plain average of 90% and 10%.
+        assert_eq!(summary.battery_percent, 67);
+        assert!(!summary.is_plugged_in);
+    }
+
+    #[test]
+    fn device_scoped_peripherals_are_ignored() {
+        let sysfs = FakeSysfs::new("peripheral");
+        sysfs
+            .supply(
+                "BAT1",
+                &[
+                    ("type", "Battery"),
+                    ("capacity", "80"),
+                    ("power_now", "5000000"),
+                ],
+            )
+            .supply(
+                "hidpp_battery_0",
+                &[
+                    ("type", "Battery"),
+                    ("scope", "Device"),
+                    ("capacity", "5"),
+                    ("power_now", "100000"),
+                ],
+            )
+            .supply(
+                "ucsi-source-psy-USBC000:001",
+                &[("type", "USB"), ("scope", "Device"), ("online", "1")],
+            );
+
+        let summary = read_summary(&sysfs);
+        assert_eq!(summary.batteries.len(), 1);
+        assert_eq!(summary.batteries[0].name, "BAT1");
+        assert_eq!(summary.battery_percent, 80);
+        assert_eq!(summary.battery_watts, 5.0);
+        assert!(!summary.is_plugged_in);
+    }
+
+    #[test]
+    fn adapters_are_found_by_type_not_name() {
+        for adapter in ["ADP1", "ACAD0", "AC"] {
+            let sysfs = FakeSysfs::new(adapter);
+            sysfs
+                .supply(adapter, &[("type", "Mains"), ("online", "1")])
+                .supply("BATT", &[("type", "Battery"), ("capacity", "50")]);
+
+            let summary = read_summary(&sysfs);
+            assert!(summary.is_plugged_in, "{} online", adapter);
+            assert_eq!(summary.batteries.len(), 1);
+            assert_eq!(summary.batteries[0].name, "BATT");
+            assert_eq!(summary.battery_percent, 50);
+
+            sysfs.supply(adapter, &[("online", "0")]);
+            assert!(!read_summary(&sysfs).is_plugged_in, "{} offline", adapter);
+        }
+    }
+
+    #[test]
+    fn charge_only_battery_is_converted_to_energy() {
+        let sysfs = FakeSysfs::new("charge-only");
+        sysfs.supply(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("voltage_min_design", "15000000"),
+                ("voltage_now", "16000000"),
+                ("current_now", "1000000"),
+                ("charge_now", "2000000"),
+                ("charge_full", "4000000"),
+                ("charge_full_design", "5000000"),
+            ],
+        );
+
+        let summary = read_summary(&sysfs);
+        let battery = &summary.batteries[0];
+        assert_eq!(battery.status, BatteryStatus::Charging);
+        // µAh × design voltage: 2 Ah × 15 V = 30 Wh.
+        assert_eq!(battery.energy_now_wh, Some(30.0));
+        assert_eq!(battery.energy_full_wh, Some(60.0));
+        assert_eq!(battery.energy_full_design_wh, Some(75.0));
+        assert_eq!(summary.battery_percent, 50);
+        // No power_now: voltage_now × current_now.
+        assert_eq!(summary.battery_watts, 16.0);
+    }
+}
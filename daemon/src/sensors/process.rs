@@ -0,0 +1,165 @@
+use super::CpuTimes;
+use nitro_core::ProcessPower;
+use std::collections::HashMap;
+use std::time::Instant;
+
+/// Raw counters for one process from `/proc/[pid]/stat` (and `sched`).
+#[derive(Debug, Clone)]
+pub struct ProcessSample {
+    pub pid: u32,
+    pub name: String,
+    /// utime + stime, in clock ticks.
+    pub cpu_ticks: u64,
+    /// `se.nr_wakeups` from `/proc/[pid]/sched`, when requested and available.
+    pub wakeups: Option<u64>,
+}
+
+impl ProcessSample {
+    /// Parses `/proc/[pid]/stat`. `comm` may contain spaces and parentheses,
+    /// so fields are counted from the last ')'.
+    pub fn parse_stat(pid: u32, stat: &str) -> Option<Self> {
+        let open = stat.find('(')?;
+        let close = stat.rfind(')')?;
+        let name = stat.get(open + 1..close)?.to_string();
+        // Fields after comm start at #3 (state); utime is #14, stime #15.
+        let fields: Vec<&str> = stat.get(close + 1..)?.split_whitespace().collect();
+        let utime: u64 = fields.get(11)?.parse().ok()?;
+        let stime: u64 = fields.get(12)?.parse().ok()?;
+        Some(Self {
+            pid,
+            name,
+            cpu_ticks: utime + stime,
+            wakeups: None,
+        })
+    }
+}
+
+/// Splits the measured CPU package power across processes by their share
+/// of all CPU time since the previous tick.
+#[derive(Default)]
+pub struct ProcessTracker {
+    prev: Option<(Instant, u64, HashMap<u32, ProcessSample>)>,
+}
+
+impl ProcessTracker {
+    /// Returns every process that ran since the last call, heaviest first.
+    /// `cpu_times` is the aggregate `cpu` line of `/proc/stat`, in the same
+    /// clock ticks as the samples.
+    pub fn update(
+        &mut self,
+        samples: Vec<ProcessSample>,
+        cpu_times: &CpuTimes,
+        cpu_watts: f32,
+    ) -> Vec<ProcessPower> {
+        let now = Instant::now();
+        let current: HashMap<u32, ProcessSample> =
+            samples.iter().map(|s| (s.pid, s.clone())).collect();
+
+        let Some((then, prev_total, prev)) = self.prev.replace((now, cpu_times.total, current))
+        else {
+            return Vec::new();
+        };
+        let secs = now.duration_since(then).as_secs_f32();
+
+        // (sample, ticks, wakeups) for processes seen in both snapshots.
+        let deltas: Vec<(&ProcessSample, u64, Option<u64>)> = samples
+            .iter()
+            .filter_map(|s| {
+                let old = prev.get(&s.pid)?;
+                let wakeups = match (s.wakeups, old.wakeups) {
+                    (Some(now), Some(then)) => Some(now.saturating_sub(then)),
+                    _ => None,
+                };
+                Some((s, s.cpu_ticks.saturating_sub(old.cpu_ticks), wakeups))
+            })
+            .collect();
+
+        // Idle time is part of the total, so only the busy fraction of
+        // cpu_watts ends up attributed to processes.
+        let total_ticks = cpu_times.total.saturating_sub(prev_total);
+        if total_ticks == 0 {
+            return Vec::new();
+        }
+
+        let mut consumers: Vec<ProcessPower> = deltas
+            .into_iter()
+            .filter(|(_, ticks, _)| *ticks > 0)
+            .map(|(s, ticks, wakeups)| {
+                let share = (ticks as f32 / total_ticks as f32).min(1.0);
+                ProcessPower {
+                    pid: s.pid,
+                    name: s.name.clone(),
+                    cpu_share: share * 100.0,
+                    watts: share * cpu_watts,
+                    wakeups_per_sec: wakeups.filter(|_| secs > 0.0).map(|w| w as f32 / secs),
+                }
+            })
+            .collect();
+        consumers.sort_by(|a, b| b.cpu_share.total_cmp(&a.cpu_share));
+        consumers
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(pid: u32, cpu_ticks: u64) -> ProcessSample {
+        ProcessSample {
+            pid,
+            name: format!("proc{}", pid),
+            cpu_ticks,
+            wakeups: None,
+        }
+    }
+
+    fn cpu(busy: u64, total: u64) -> CpuTimes {
+        CpuTimes { busy, total }
+    }
+
+    #[test]
+    fn parses_comm_with_spaces_and_parentheses() {
+        let stat = "1234 (Web Content (x)) S 1 1234 1234 0 -1 4194560 \
+                    100 0 0 0 250 75 0 0 20 0 30 0 12345 0 0";
+        let sample = ProcessSample::parse_stat(1234, stat).unwrap();
+        assert_eq!(sample.name, "Web Content (x)");
+        assert_eq!(sample.cpu_ticks, 325);
+    }
+
+    #[test]
+    fn rejects_truncated_stat() {
+        assert!(ProcessSample::parse_stat(1, "1 (init) S 0 1").is_none());
+    }
+
+    #[test]
+    fn shares_are_measured_against_all_cpu_time() {
+        let mut tracker = ProcessTracker::default();
+        assert!(tracker
+            .update(vec![sample(1, 0), sample(2, 0)], &cpu(0, 0), 20.0)
+            .is_empty());
+
+        // 800 jiffies across all CPUs, 200 of them busy: 150 + 50 by our two
+        let consumers = tracker.update(
+            vec![sample(1, 150), sample(2, 50), sample(3, 999)],
+            &cpu(200, 800),
+            20.0,
+        );
+        assert_eq!(consumers.len(), 2, "new processes have no delta yet");
+        assert_eq!(consumers[0].pid, 1);
+        assert_eq!(consumers[0].cpu_share, 18.75);
+        assert_eq!(consumers[1].cpu_share, 6.25);
+        // Only the busy quarter of the 20 W is handed out
+        let watts: f32 = consumers.iter().map(|c| c.watts).sum();
+        assert_eq!(watts, 5.0);
+    }
+
+    #[test]
+    fn idle_processes_are_left_out() {
+        let mut tracker = ProcessTracker::default();
+        tracker.update(vec![sample(1, 10), sample(2, 10)], &cpu(0, 0), 10.0);
+        let consumers = tracker.update(vec![sample(1, 10), sample(2, 30)], &cpu(20, 100), 10.0);
+        assert_eq!(consumers.len(), 1);
+        assert_eq!(consumers[0].pid, 2);
+        assert_eq!(consumers[0].cpu_share, 20.0);
+    }
+}
@@ -0,0 +1,25 @@
+use nitro_core::RadioState;
+
+/// One `/sys/class/rfkill/rfkill*` switch.
+#[derive(Debug, Clone)]
+pub struct RfkillDevice {
+    /// "wlan", "bluetooth", "wwan", ...
+    pub kind: String,
+    pub soft_blocked: bool,
+    pub hard_blocked: bool,
+}
+
+/// Combined state of every switch of `kind`. A hardware kill switch wins
+/// over everything, since software can't undo it.
+pub fn radio_state(devices: &[RfkillDevice], kind: &str) -> RadioState {
+    let matching: Vec<&RfkillDevice> = devices.iter().filter(|d| d.kind == kind).collect();
+    if matching.is_empty() {
+        RadioState::Absent
+    } else if matching.iter().any(|d| d.hard_blocked) {
+        RadioState::HardBlocked
+    } else if matching.iter().all(|d| d.soft_blocked) {
+        RadioState::SoftBlocked
+    } else {
+        RadioState::On
+    }
+}
@@ -0,0 +1,320 @@
+use super::{
+    pm_table, CpuTimes, EnergyCounter, PmTable, PowerSupply, ProcessSample, RfkillDevice,
+    SensorBackend, SupplyKind,
+};
+use crate::attr::{self, read_string};
+use nitro_core::{GpuState, Temperature};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Reads sensors from a sysfs/procfs tree mounted under `root`.
+///
+/// On real hardware `root` is `/`. Pointing it somewhere else lets the
+/// daemon run against a captured or hand-made tree.
+pub struct SysfsBackend {
+    root: PathBuf,
+}
+
+impl SysfsBackend {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Resolves an absolute system path (e.g. `/sys/class/...`) under our root.
+    fn path(&self, abs: &str) -> PathBuf {
+        self.root.join(abs.trim_start_matches('/'))
+    }
+
+    /// Sorted entries of a directory under our root. Empty if it doesn't exist.
+    fn list_dir(&self, abs: &str) -> Vec<PathBuf> {
+        let mut dirs: Vec<PathBuf> = match fs::read_dir(self.path(abs)) {
+            Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+            Err(_) => Vec::new(),
+        };
+        dirs.sort();
+        dirs
+    }
+
+    fn powercap_counters(&self) -> Vec<EnergyCounter> {
+        self.list_dir("/sys/class/powercap")
+            .iter()
+            // intel-rapl-mmio:* mirrors the MSR package zone; counting both
+            // would double the reading.
+            .filter(|zone| !zone.to_string_lossy().contains("intel-rapl-mmio"))
+            .filter(|zone| {
+                read_string(&zone.join("name")).is_some_and(|name| name.starts_with("package"))
+            })
+            .filter_map(|zone| {
+                Some(EnergyCounter {
+                    id: zone.file_name()?.to_string_lossy().into_owned(),
+                    energy_uj: read_attr(zone, "energy_uj")?,
+                    max_energy_uj: read_attr(zone, "max_energy_range_uj"),
+                })
+            })
+            .collect()
+    }
+
+    fn hwmon_counters(&self) -> Vec<EnergyCounter> {
+        let mut counters = Vec::new();
+        for hwmon in self.list_dir("/sys/class/hwmon") {
+            let Some(name) = read_string(&hwmon.join("name")) else {
+                continue;
+            };
+            if name != "amd_energy" && name != "zenpower" {
+                continue;
+            }
+
+            // amd_energy exposes one channel per core plus one per socket
+            // ("Esocket0"). Only count sockets when they are labelled.
+            let channels: Vec<(String, Option<String>)> = fs::read_dir(&hwmon)
+                .into_iter()
+                .flatten()
+                .flatten()
+                .filter_map(|e| {
+                    let file = e.file_name().to_string_lossy().into_owned();
+                    let channel = file.strip_suffix("_input")?.to_string();
+                    channel.starts_with("energy").then_some(channel)
+                })
+                .map(|channel| {
+                    let label = read_string(&hwmon.join(format!("{}_label", channel)));
+                    (channel, label)
+                })
+                .collect();
+            let has_sockets = channels
+                .iter()
+                .any(|(_, label)| label.as_deref().is_some_and(|l| l.contains("socket")));
+
+            for (channel, label) in channels {
+                if has_sockets && !label.is_some_and(|l| l.contains("socket")) {
+                    continue;
+                }
+                if let Some(energy_uj) = read_attr(&hwmon, &format!("{}_input", channel)) {
+                    counters.push(EnergyCounter {
+                        id: format!("{}/{}", name, channel),
+                        energy_uj,
+                        max_energy_uj: None,
+                    });
+                }
+            }
+        }
+        counters
+    }
+
+    fn hwmon_temperatures(&self) -> Vec<Temperature> {
+        let mut temps = Vec::new();
+        for hwmon in self.list_dir("/sys/class/hwmon") {
+            let Some(name) = read_string(&hwmon.join("name")) else {
+                continue;
+            };
+            // CPU sensors are labelled on their own (Tctl, Tccd1, ...); everything
+            // else gets the driver name as prefix to tell the chips apart.
+            let cpu_sensor = match name.as_str() {
+                "k10temp" | "zenpower" => true,
+                "nvme" | "amdgpu" | "nouveau" => false,
+                _ => continue,
+            };
+
+            for n in 1..=16 {
+                let Some(millis) = read_attr::<f32>(&hwmon, &format!("temp{}_input", n)) else {
+                    continue;
+                };
+                let label = read_string(&hwmon.join(format!("temp{}_label", n)));
+                let label = match (cpu_sensor, label) {
+                    (true, Some(label)) => label,
+                    (false, Some(label)) => format!("{} {}", name, label),
+                    (_, None) => name.clone(),
+                };
+                temps.push(Temperature {
+                    label,
+                    celsius: millis / 1000.0,
+                });
+            }
+        }
+        temps
+    }
+
+    fn thermal_zone_temperatures(&self) -> Vec<Temperature> {
+        self.list_dir("/sys/class/thermal")
+            .iter()
+            .filter(|zone| zone.to_string_lossy().contains("thermal_zone"))
+            .filter_map(|zone| {
+                Some(Temperature {
+                    label: read_string(&zone.join("type"))?,
+                    celsius: read_attr::<f32>(zone, "temp")? / 1000.0,
+                })
+            })
+            .collect()
+    }
+
+    fn read_power_supply(&self, dir: &Path) -> Option<PowerSupply> {
+        let name = dir.file_name()?.to_string_lossy().into_owned();
+        let kind = SupplyKind::from_type(&read_string(&dir.join("type"))?);
+        let is_system = read_string(&dir.join("scope")).as_deref() != Some("Device");
+
+        let voltage_now = read_attr(dir, "voltage_now");
+        // Batteries reporting µAh (charge_*) instead of µWh (energy_*) are
+        // converted using the design voltage, or the current one if missing.
+        let voltage = read_attr(dir, "voltage_min_design").or(voltage_now);
+        let energy = |attr: &str| {
+            read_attr::<f32>(dir, &format!("energy_{}", attr)).or_else(|| {
+                let charge = read_attr::<f32>(dir, &format!("charge_{}", attr))?;
+                Some(charge * voltage? / 1_000_000.0)
+            })
+        };
+
+        Some(PowerSupply {
+            kind,
+            is_system,
+            online: read_attr::<u8>(dir, "online").map(|v| v == 1),
+            capacity: read_attr(dir, "capacity"),
+            power_now: read_attr(dir, "power_now"),
+            voltage_now,
+            current_now: read_attr(dir, "current_now"),
+            energy_now: energy("now"),
+            energy_full: energy("full"),
+            energy_full_design: energy("full_design"),
+            status: read_string(&dir.join("status")),
+            cycle_count: read_attr(dir, "cycle_count"),
+            technology: read_string(&dir.join("technology")),
+            manufacturer: read_string(&dir.join("manufacturer")),
+            model_name: read_string(&dir.join("model_name")),
+            name,
+        })
+    }
+}
+
+impl SensorBackend for SysfsBackend {
+    fn power_supplies(&self) -> Vec<PowerSupply> {
+        let class_dir = self.path("/sys/class/power_supply");
+        let mut dirs: Vec<PathBuf> = match fs::read_dir(&class_dir) {
+            Ok(entries) => entries.flatten().map(|e| e.path()).collect(),
+            Err(e) => {
+                log::error!("Failed to list {}: {}", class_dir.display(), e);
+                return Vec::new();
+            }
+        };
+        dirs.sort();
+
+        dirs.iter()
+            .filter_map(|dir| self.read_power_supply(dir))
+            .collect()
+    }
+
+    fn cpu_times(&self) -> Vec<CpuTimes> {
+        let Some(stat) = read_string(&self.path("/proc/stat")) else {
+            log::error!("Failed to read /proc/stat");
+            return Vec::new();
+        };
+        stat.lines()
+            .take_while(|line| line.starts_with("cpu"))
+            .filter_map(CpuTimes::parse_line)
+            .collect()
+    }
+
+    fn load_average(&self) -> f32 {
+        read_string(&self.path("/proc/loadavg"))
+            .and_then(|s| s.split_whitespace().next()?.parse().ok())
+            .unwrap_or(0.0)
+    }
+
+    fn energy_counters(&self) -> Vec<EnergyCounter> {
+        let counters = self.powercap_counters();
+        if !counters.is_empty() {
+            return counters;
+        }
+        self.hwmon_counters()
+    }
+
+    fn temperatures(&self) -> Vec<Temperature> {
+        let mut temps = self.hwmon_temperatures();
+        temps.extend(self.thermal_zone_temperatures());
+        temps
+    }
+
+    fn processes(&self, with_wakeups: bool) -> Vec<ProcessSample> {
+        let Ok(entries) = fs::read_dir(self.path("/proc")) else {
+            log::error!("Failed to list /proc");
+            return Vec::new();
+        };
+
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let pid: u32 = entry.file_name().to_str()?.parse().ok()?;
+                // Processes may exit between listing and reading; just skip them.
+                let stat = fs::read_to_string(entry.path().join("stat")).ok()?;
+                let mut sample = ProcessSample::parse_stat(pid, &stat)?;
+                if with_wakeups {
+                    sample.wakeups = read_sched_wakeups(&entry.path().join("sched"));
+                }
+                Some(sample)
+            })
+            .collect()
+    }
+
+    fn discrete_gpus(&self) -> Vec<GpuState> {
+        self.list_dir("/sys/bus/pci/devices")
+            .iter()
+            .filter(|dev| {
+                // PCI class 0x03xxxx is "display controller"; the iGPU is the boot VGA device.
+                read_string(&dev.join("class")).is_some_and(|c| c.starts_with("0x03"))
+                    && read_string(&dev.join("boot_vga")).as_deref() != Some("1")
+            })
+            .filter_map(|dev| {
+                let vendor = match read_string(&dev.join("vendor"))?.as_str() {
+                    "0x10de" => "NVIDIA".to_string(),
+                    "0x1002" => "AMD".to_string(),
+                    "0x8086" => "Intel".to_string(),
+                    other => other.to_string(),
+                };
+                Some(GpuState {
+                    pci_address: dev.file_name()?.to_string_lossy().into_owned(),
+                    vendor,
+                    power_state: read_string(&dev.join("power_state"))
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    runtime_status: read_string(&dev.join("power/runtime_status"))
+                        .unwrap_or_else(|| "unknown".to_string()),
+                    runtime_active_ms: read_attr(dev, "power/runtime_active_time"),
+                })
+            })
+            .collect()
+    }
+
+    fn rfkill_devices(&self) -> Vec<RfkillDevice> {
+        self.list_dir("/sys/class/rfkill")
+            .iter()
+            .filter_map(|dev| {
+                Some(RfkillDevice {
+                    kind: read_string(&dev.join("type"))?,
+                    soft_blocked: read_attr::<u8>(dev, "soft")? == 1,
+                    hard_blocked: read_attr::<u8>(dev, "hard")? == 1,
+                })
+            })
+            .collect()
+    }
+
+    fn pm_table(&self) -> Option<PmTable> {
+        let dir = self.path("/sys/kernel/ryzen_smu_drv");
+        let version = pm_table::parse_version(&fs::read(dir.join("pm_table_version")).ok()?)?;
+        let data = fs::read(dir.join("pm_table")).ok()?;
+        Some(PmTable { version, data })
+    }
+}
+
+/// `se.nr_wakeups  :  1234` from `/proc/[pid]/sched` (needs CONFIG_SCHED_DEBUG).
+fn read_sched_wakeups(path: &Path) -> Option<u64> {
+    fs::read_to_string(path)
+        .ok()?
+        .lines()
+        .find(|line| line.starts_with("se.nr_wakeups "))?
+        .split(':')
+        .nth(1)?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn read_attr<T: FromStr>(dir: &Path, attr: &str) -> Option<T> {
+    attr::read(&dir.join(attr))
+}
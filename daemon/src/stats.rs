@@ -0,0 +1,47 @@
+use std::fs;
+use std::time::Instant;
+
+/// The daemon's own wakeup rate, from the context switches of its threads.
+#[derive(Default)]
+pub struct WakeupMeter {
+    last: Option<(u64, Instant)>,
+}
+
+impl WakeupMeter {
+    /// Wakeups per second since the previous call; 0.0 on the first.
+    pub fn update(&mut self) -> f32 {
+        let now = Instant::now();
+        let switches = context_switches();
+        let rate = match self.last {
+            Some((prev, at)) => {
+                let secs = now.duration_since(at).as_secs_f32();
+                if secs > 0.0 {
+                    switches.saturating_sub(prev) as f32 / secs
+                } else {
+                    0.0
+                }
+            }
+            None => 0.0,
+        };
+        self.last = Some((switches, now));
+        rate
+    }
+}
+
+/// Sum of (non)voluntary context switches over all of our threads. Always
+/// the real /proc: this is about the daemon, not the machine being watched.
+fn context_switches() -> u64 {
+    fs::read_dir("/proc/self/task")
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|task| fs::read_to_string(task.path().join("status")).ok())
+        .flat_map(|status| {
+            status
+                .lines()
+                .filter(|line| line.contains("ctxt_switches:"))
+                .filter_map(|line| line.split(':').nth(1)?.trim().parse::<u64>().ok())
+                .collect::<Vec<_>>()
+        })
+        .sum()
+}
@@ -0,0 +1,233 @@
+use std::collections::HashMap;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use tokio::io::unix::AsyncFd;
+use tokio::sync::mpsc;
+
+const RECV_BUFFER_BYTES: libc::c_int = 1024 * 1024;
+
+/// An AC adapter (Mains/USB power_supply) changed state.
+#[derive(Debug, Clone)]
+pub struct AdapterEvent {
+    pub name: String,
+    pub online: Option<bool>,
+}
+
+/// Listens for kernel power_supply uevents and forwards adapter changes.
+///
+/// If the netlink socket can't be opened (e.g. no permission in a
+/// container) this just logs and returns; the governor keeps polling.
+pub fn spawn_listener(tx: mpsc::Sender<AdapterEvent>) {
+    tokio::spawn(async move {
+        let socket = match open_socket() {
+            Ok(socket) => socket,
+            Err(e) => {
+                log::warn!("uevent socket unavailable, relying on polling: {}", e);
+                return;
+            }
+        };
+        log::info!("Listening for power_supply uevents");
+
+        let mut buf = vec![0u8; 8192];
+        loop {
+            let len = match recv(&socket, &mut buf).await {
+                Ok(len) => len,
+                // A burst overflowed the socket buffer and events were
+                // dropped; the next 2 s poll re-reads the supplies anyway.
+                Err(e) if e.raw_os_error() == Some(libc::ENOBUFS) => {
+                    log::debug!("uevent socket overrun, some events were dropped");
+                    continue;
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    log::error!("uevent socket failed, relying on polling: {}", e);
+                    return;
+                }
+            };
+            if let Some(event) = parse_adapter_event(&buf[..len]) {
+                if tx.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+}
+
+fn open_socket() -> io::Result<AsyncFd<OwnedFd>> {
+    // SAFETY: plain socket(2)/bind(2) calls; the fd is owned by OwnedFd
+    // right after creation so it is closed on every error path.
+    unsafe {
+        let fd = libc::socket(
+            libc::AF_NETLINK,
+            libc::SOCK_DGRAM | libc::SOCK_NONBLOCK | libc::SOCK_CLOEXEC,
+            libc::NETLINK_KOBJECT_UEVENT,
+        );
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = OwnedFd::from_raw_fd(fd);
+
+        // Battery updates from every supply share this socket; a bigger
+        // buffer makes overruns during bursts (e.g. on resume) rarer.
+        // Failing to grow it is harmless.
+        let size: libc::c_int = RECV_BUFFER_BYTES;
+        libc::setsockopt(
+            fd.as_raw_fd(),
+            libc::SOL_SOCKET,
+            libc::SO_RCVBUF,
+            &size as *const libc::c_int as *const libc::c_void,
+            std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+        );
+
+        let mut addr: libc::sockaddr_nl = std::mem::zeroed();
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = 1; // Kernel events (udev re-broadcasts on group 2)
+        let ret = libc::bind(
+            fd.as_raw_fd(),
+            &addr as *const libc::sockaddr_nl as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+        );
+        if ret < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        AsyncFd::new(fd)
+    }
+}
+
+async fn recv(socket: &AsyncFd<OwnedFd>, buf: &mut [u8]) -> io::Result<usize> {
+    loop {
+        let mut guard = socket.readable().await?;
+        let result = guard.try_io(|fd| {
+            // SAFETY: buf is valid for buf.len() bytes for the duration of the call
+            let n = unsafe {
+                libc::recv(
+                    fd.as_raw_fd(),
+                    buf.as_mut_ptr() as *mut libc::c_void,
+                    buf.len(),
+                    0,
+                )
+            };
+            if n < 0 {
+                Err(io::Error::last_os_error())
+            } else {
+                Ok(n as usize)
+            }
+        });
+        match result {
+            Ok(result) => return result,
+            Err(_would_block) => continue,
+        }
+    }
+}
+
+/// A uevent is `action@devpath\0KEY=VALUE\0KEY=VALUE\0...`.
+/// Battery updates arrive every few seconds as well; only adapters matter here.
+fn parse_adapter_event(msg: &[u8]) -> Option<AdapterEvent> {
+    let fields: HashMap<&str, &str> = msg
+        .split(|b| *b == 0)
+        .filter_map(|field| std::str::from_utf8(field).ok()?.split_once('='))
+        .collect();
+
+    if fields.get("SUBSYSTEM") != Some(&"power_supply") {
+        return None;
+    }
+    let is_adapter = match fields.get("POWER_SUPPLY_TYPE") {
+        Some(kind) => *kind == "Mains" || kind.starts_with("USB"),
+        None => fields.contains_key("POWER_SUPPLY_ONLINE"),
+    };
+    if !is_adapter {
+        return None;
+    }
+
+    Some(AdapterEvent {
+        name: fields.get("POWER_SUPPLY_NAME").unwrap_or(&"?").to_string(),
+        online: fields.get("POWER_SUPPLY_ONLINE").map(|v| *v == "1"),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uevent(fields: &[&str]) -> Vec<u8> {
+        let mut msg = b"change@/devices/LNXSYSTM:00/ACPI0003:00/power_supply/ACAD0".to_vec();
+        for field in fields {
+            msg.push(0);
+            msg.extend_from_slice(field.as_bytes());
+        }
+        msg.push(0);
+        msg
+    }
+
+    #[test]
+    fn mains_adapter_is_reported() {
+        let event = parse_adapter_event(&uevent(&[
+            "ACTION=change",
+            "SUBSYSTEM=power_supply",
+            "POWER_SUPPLY_NAME=ACAD0",
+            "POWER_SUPPLY_TYPE=Mains",
+            "POWER_SUPPLY_ONLINE=0",
+        ]))
+        .unwrap();
+        assert_eq!(event.name, "ACAD0");
+        assert_eq!(event.online, Some(false));
+    }
+
+    #[test]
+    fn usb_types_are_adapters() {
+        for kind in ["USB", "USB_PD", "USB_C"] {
+            let event = parse_adapter_event(&uevent(&[
+                "SUBSYSTEM=power_supply",
+                "POWER_SUPPLY_NAME=ucsi-source-psy-USBC000:001",
+                &format!("POWER_SUPPLY_TYPE={}", kind),
+                "POWER_SUPPLY_ONLINE=1",
+            ]))
+            .unwrap();
+            assert_eq!(event.online, Some(true), "{}", kind);
+        }
+    }
+
+    #[test]
+    fn adapter_without_type_is_recognised_by_online() {
+        let event = parse_adapter_event(&uevent(&[
+            "SUBSYSTEM=power_supply",
+            "POWER_SUPPLY_NAME=AC",
+            "POWER_SUPPLY_ONLINE=1",
+        ]))
+        .unwrap();
+        assert_eq!(event.name, "AC");
+        assert_eq!(event.online, Some(true));
+    }
+
+    #[test]
+    fn adapter_without_online_is_a_change() {
+        let event = parse_adapter_event(&uevent(&[
+            "SUBSYSTEM=power_supply",
+            "POWER_SUPPLY_TYPE=Mains",
+        ]))
+        .unwrap();
+        assert_eq!(event.name, "?");
+        assert_eq!(event.online, None);
+    }
+
+    #[test]
+    fn batteries_and_other_subsystems_are_ignored() {
+        assert!(parse_adapter_event(&uevent(&[
+            "SUBSYSTEM=power_supply",
+            "POWER_SUPPLY_NAME=BAT0",
+            "POWER_SUPPLY_TYPE=Battery",
+            "POWER_SUPPLY_CAPACITY=80",
+        ]))
+        .is_none());
+        assert!(parse_adapter_event(&uevent(&[
+            "SUBSYSTEM=power_supply",
+            "POWER_SUPPLY_NAME=BAT0",
+            "POWER_SUPPLY_CAPACITY=80",
+        ]))
+        .is_none());
+        assert!(
+            parse_adapter_event(&uevent(&["SUBSYSTEM=usb", "POWER_SUPPLY_ONLINE=1"])).is_none()
+        );
+        assert!(parse_adapter_event(b"").is_none());
+    }
+}
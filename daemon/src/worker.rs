@@ -0,0 +1,124 @@
+use crate::actuator::{self, Actuator};
+use crate::config::NitroConfig;
+use crate::display::DisplayActuator;
+use crate::gpu::GpuSwitch;
+use nitro_core::{GpuState, Profile, RyzenInfo};
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
+
+/// What the governor wants the hardware to look like, sent every tick.
+#[derive(Debug, Clone)]
+pub struct HardwareTarget {
+    pub profile: Profile,
+    pub is_plugged_in: bool,
+    /// The newest SMU readings, for the drift check.
+    pub readings: Option<RyzenInfo>,
+    /// When `readings` were taken; `ryzenadj -i` ones can be a tick old.
+    pub readings_at: Option<Instant>,
+    pub gpus: Vec<GpuState>,
+    /// Bumped on every resume. A counter rather than a flag, so a resume is
+    /// not lost when the worker skips targets it was too busy to see.
+    pub resumes: u64,
+}
+
+/// What the worker last did, for the published `PowerState`.
+#[derive(Debug, Clone, Default)]
+pub struct HardwareReport {
+    pub limits_in_sync: Option<bool>,
+    pub revert_count: u32,
+    pub last_revert_at: Option<u64>,
+    pub limit_applies: u64,
+    pub skipped_applies: u64,
+    pub dgpu_blockers: Vec<u32>,
+    /// The first actuator error that is still standing, e.g. limits that
+    /// failed to apply and haven't been applied since.
+    pub last_error: Option<String>,
+}
+
+/// Runs everything that forks (ryzenadj, compositor commands, supergfxctl)
+/// on its own task, so a hung command can't stall the governor's 2 s tick.
+///
+/// Targets go through a watch channel: while a command is running, newer
+/// targets replace older ones and only the latest is acted on.
+pub fn spawn(
+    config: NitroConfig,
+    mut target_rx: watch::Receiver<Option<HardwareTarget>>,
+    report_tx: watch::Sender<HardwareReport>,
+) {
+    tokio::spawn(async move {
+        let mut actuator = Actuator::new(config.clone());
+        let mut display = config.display.clone().map(DisplayActuator::new);
+        let mut gpu_switch = GpuSwitch::new(
+            &config.sysfs_root,
+            Duration::from_secs(config.paranoid_interval_secs),
+        );
+        let mut resumes = 0;
+        let mut limits_error = None;
+
+        while target_rx.changed().await.is_ok() {
+            let Some(target) = target_rx.borrow_and_update().clone() else {
+                continue;
+            };
+
+            if target.resumes != resumes {
+                resumes = target.resumes;
+                actuator.after_resume();
+                // Firmware and the compositor may have reset these while asleep
+                if let Some(display) = display.as_mut() {
+                    display.reset();
+                }
+                gpu_switch.reset();
+            }
+
+            // Readings from before the last apply would look like drift
+            let readings = match (target.readings_at, actuator.last_apply()) {
+                (Some(taken), Some(applied)) if taken < applied => None,
+                _ => target.readings.as_ref(),
+            };
+            let (applies_before, _) = actuator.apply_counts();
+            let result = actuator
+                .apply_profile(&target.profile, target.is_plugged_in, readings)
+                .await;
+            if let Err(e) = &result {
+                log::error!("Failed to apply power limits: {:#}", e);
+            }
+            // A skipped apply says nothing about whether the last one worked
+            if actuator.apply_counts().0 != applies_before || result.is_err() {
+                limits_error = result.err().map(|e| format!("power limits: {:#}", e));
+            }
+
+            // Refresh rate and dGPU mode follow the effective profile (Pro on AC)
+            let effective = actuator::target_profile(&target.profile, target.is_plugged_in);
+            if let (Some(display), Some(rate)) =
+                (display.as_mut(), config.profile(&effective).refresh_rate)
+            {
+                display.apply(rate).await;
+            }
+            if let Some(mode) = config.profile(&effective).gpu_mode {
+                if !target.gpus.is_empty() {
+                    if let Err(e) = gpu_switch.apply(mode, &target.gpus).await {
+                        log::error!("Failed to switch dGPU mode: {:#}", e);
+                    }
+                }
+            }
+
+            let last_error = limits_error
+                .clone()
+                .or_else(|| {
+                    let error = display.as_ref()?.last_error()?;
+                    Some(format!("refresh rate: {}", error))
+                })
+                .or_else(|| Some(format!("dGPU switch: {}", gpu_switch.last_error()?)));
+            let (limit_applies, skipped_applies) = actuator.apply_counts();
+            report_tx.send_replace(HardwareReport {
+                limits_in_sync: actuator.limits_in_sync(),
+                revert_count: actuator.revert_count(),
+                last_revert_at: actuator.last_revert_at(),
+                limit_applies,
+                skipped_applies,
+                dgpu_blockers: gpu_switch.blockers().to_vec(),
+                last_error,
+            });
+        }
+    });
+}
@@ -4,7 +4,9 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use nitro_core::{DaemonCommand, PowerState, Profile};
+use nitro_core::{
+    DaemonCommand, DaemonResponse, PlannedAction, PowerState, ProcessPower, Profile, RadioState,
+};
 use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
@@ -20,6 +22,9 @@ use tokio::net::UnixStream;
 use tokio::sync::mpsc;
 use tokio::time;
 
+/// How many processes the Top Consumers panel asks for.
+const TOP_CONSUMERS: usize = 8;
+
 #[tokio::main]
 async fn main() -> Result<()> {
     // Setup Terminal
@@ -31,6 +36,7 @@ async fn main() -> Result<()> {
 
     // Channels
     let (tx_state, mut rx_state) = mpsc::channel::<PowerState>(10);
+    let (tx_resp, mut rx_resp) = mpsc::channel::<DaemonResponse>(10);
     let (tx_cmd, mut rx_cmd) = mpsc::channel::<DaemonCommand>(10);
 
     // Spawn Network Task
@@ -42,9 +48,15 @@ async fn main() -> Result<()> {
 
                 // Reader Task
                 let tx_state = tx_state.clone();
+                let tx_resp = tx_resp.clone();
                 let mut reader_handle = tokio::spawn(async move {
                     while let Ok(Some(line)) = lines.next_line().await {
-                        if let Ok(state) = serde_json::from_str::<PowerState>(&line) {
+                        // Replies are tagged enums, so try them first
+                        if let Ok(resp) = serde_json::from_str::<DaemonResponse>(&line) {
+                            if tx_resp.send(resp).await.is_err() {
+                                break;
+                            }
+                        } else if let Ok(state) = serde_json::from_str::<PowerState>(&line) {
                             if tx_state.send(state).await.is_err() {
                                 break;
                             }
@@ -80,16 +92,9 @@ async fn main() -> Result<()> {
     });
 
     // App Loop
-    let mut state = PowerState {
-        battery_watts: 0.0,
-        cpu_watts: 0.0,
-        battery_percent: 0,
-        cpu_load: 0.0,
-        profile: Profile::Eco,
-        wifi_on: false,
-        bluetooth_on: false,
-        is_plugged_in: false,
-    };
+    let mut state = PowerState::default();
+    let mut top_consumers: Vec<ProcessPower> = Vec::new();
+    let mut planned: Vec<PlannedAction> = Vec::new();
 
     let tick_rate = Duration::from_millis(100);
     let mut last_tick = time::Instant::now();
@@ -117,8 +122,12 @@ async fn main() -> Result<()> {
                 Color::Red
             };
 
+            let battery_title = match state.batteries.first() {
+                Some(battery) => format!("Battery ({:?})", battery.status),
+                None => "Battery".to_string(),
+            };
             let gauge = Gauge::default()
-                .block(Block::default().title("Battery").borders(Borders::ALL))
+                .block(Block::default().title(battery_title).borders(Borders::ALL))
                 .gauge_style(Style::default().fg(gauge_color))
                 .percent(state.battery_percent as u16);
             f.render_widget(gauge, chunks[0]);
@@ -126,13 +135,23 @@ async fn main() -> Result<()> {
             // 2. Wattage
             // 2. Wattage
             let watt_text = if state.is_plugged_in {
-                vec![Span::styled(
-                    "CHARGING",
-                    Style::default()
-                        .fg(Color::Cyan)
-                        .add_modifier(Modifier::BOLD)
-                        .add_modifier(Modifier::ITALIC),
-                )]
+                vec![
+                    Span::styled(
+                        "CHARGING",
+                        Style::default()
+                            .fg(Color::Cyan)
+                            .add_modifier(Modifier::BOLD)
+                            .add_modifier(Modifier::ITALIC),
+                    ),
+                    Span::raw("\n"),
+                    Span::styled(
+                        match state.time_to_full {
+                            Some(secs) => format!("FULL IN: {}", format_duration(secs)),
+                            None => "FULL IN: --".to_string(),
+                        },
+                        Style::default().fg(Color::Green),
+                    ),
+                ]
             } else {
                 vec![
                     Span::styled(
@@ -148,6 +167,14 @@ async fn main() -> Result<()> {
                             .fg(Color::Yellow)
                             .add_modifier(Modifier::BOLD),
                     ),
+                    Span::raw("\n"),
+                    Span::styled(
+                        match state.time_to_empty {
+                            Some(secs) => format!("LEFT:  {}", format_duration(secs)),
+                            None => "LEFT:  --".to_string(),
+                        },
+                        Style::default().fg(Color::Green),
+                    ),
                 ]
             };
 
@@ -159,34 +186,271 @@ async fn main() -> Result<()> {
             ))
             .block(Block::default().title("Power Draw").borders(Borders::ALL))
             .alignment(ratatui::layout::Alignment::Center);
-            f.render_widget(paragraph, chunks[1]);
+
+            let middle = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+                .split(chunks[1]);
+            f.render_widget(paragraph, middle[0]);
+
+            // Top Consumers
+            let consumer_rows = top_consumers.iter().map(|p| {
+                Row::new(vec![
+                    Cell::from(p.name.clone()),
+                    Cell::from(format!("{:.1} W", p.watts)),
+                    Cell::from(format!("{:.0}%", p.cpu_share)),
+                    Cell::from(
+                        p.wakeups_per_sec
+                            .map(|w| format!("{:.0}/s", w))
+                            .unwrap_or_default(),
+                    ),
+                ])
+            });
+            let consumers = Table::new(
+                consumer_rows,
+                [
+                    Constraint::Percentage(40),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                    Constraint::Percentage(20),
+                ],
+            )
+            .block(
+                Block::default()
+                    .title("Top Consumers")
+                    .borders(Borders::ALL),
+            );
+            f.render_widget(consumers, middle[1]);
 
             // 3. Details Table
-            let rows = vec![
+            let mut rows = vec![Row::new(vec![
+                Cell::from("Profile"),
+                Cell::from(format!("{:?}", state.profile)),
+            ])];
+
+            // Right below the profile, so the awake-on-battery warning is never clipped
+            if let Some(gpu) = &state.dgpu_state {
+                // Truth table: the dGPU must be off on battery
+                let awake_on_battery = !gpu.is_off() && !state.is_plugged_in;
+                rows.push(
+                    Row::new(vec![
+                        Cell::from(format!("dGPU ({})", gpu.vendor)),
+                        Cell::from(format!("{} ({})", gpu.power_state, gpu.runtime_status)),
+                    ])
+                    .style(if awake_on_battery {
+                        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default()
+                    }),
+                );
+                if !state.dgpu_blockers.is_empty() {
+                    rows.push(
+                        Row::new(vec![
+                            Cell::from("dGPU held by"),
+                            Cell::from(
+                                state
+                                    .dgpu_blockers
+                                    .iter()
+                                    .map(|pid| pid.to_string())
+                                    .collect::<Vec<_>>()
+                                    .join(", "),
+                            ),
+                        ])
+                        .style(Style::default().fg(Color::Yellow)),
+                    );
+                }
+            }
+
+            rows.extend([
                 Row::new(vec![
-                    Cell::from("Profile"),
-                    Cell::from(format!("{:?}", state.profile)),
+                    Cell::from("CPU Usage"),
+                    Cell::from(format!("{:.0}%", state.cpu_util_percent)),
                 ]),
                 Row::new(vec![
-                    Cell::from("CPU Load"),
+                    Cell::from("Per Core"),
+                    Cell::from(
+                        state
+                            .per_core_util
+                            .iter()
+                            .map(|u| format!("{:.0}", u))
+                            .collect::<Vec<_>>()
+                            .join(" "),
+                    ),
+                ]),
+                Row::new(vec![
+                    Cell::from("Load Avg"),
                     Cell::from(format!("{:.2}", state.cpu_load)),
                 ]),
                 Row::new(vec![
                     Cell::from("WiFi"),
-                    Cell::from(if state.wifi_on { "ON" } else { "OFF" }),
+                    Cell::from(radio_label(&state.wifi)),
                 ]),
                 Row::new(vec![
                     Cell::from("Bluetooth"),
-                    Cell::from(if state.bluetooth_on { "ON" } else { "OFF" }),
+                    Cell::from(radio_label(&state.bluetooth)),
                 ]),
-            ];
+            ]);
+
+            // Tuning and daemon diagnostics last: on a short terminal they
+            // are the rows that get clipped
+            for battery in &state.batteries {
+                let wear = battery
+                    .wear_percent()
+                    .map(|w| format!("{:.1}% wear", w))
+                    .unwrap_or_else(|| "wear n/a".to_string());
+                let cycles = battery
+                    .cycle_count
+                    .map(|c| format!(", {} cycles", c))
+                    .unwrap_or_default();
+                rows.push(Row::new(vec![
+                    Cell::from(battery.name.clone()),
+                    Cell::from(format!("{:?}, {}{}", battery.status, wear, cycles)),
+                ]));
+            }
+
+            if !state.paused_containers.is_empty() {
+                rows.push(Row::new(vec![
+                    Cell::from("Paused"),
+                    Cell::from(state.paused_containers.join(", ")),
+                ]));
+            }
+
+            if let Some(freq) = &state.cpufreq {
+                let mut parts: Vec<String> = [&freq.governor, &freq.energy_performance_preference]
+                    .into_iter()
+                    .flatten()
+                    .cloned()
+                    .collect();
+                if let Some(boost) = freq.boost {
+                    parts.push(format!("boost {}", if boost { "on" } else { "off" }));
+                }
+                if let Some(khz) = freq.scaling_max_freq {
+                    parts.push(format!("max {:.1} GHz", khz as f32 / 1_000_000.0));
+                }
+                rows.push(Row::new(vec![
+                    Cell::from("CPU Freq"),
+                    Cell::from(parts.join(", ")),
+                ]));
+            }
+
+            if let Some(platform_profile) = &state.platform_profile {
+                rows.push(Row::new(vec![
+                    Cell::from("Platform"),
+                    Cell::from(match &state.amd_pstate {
+                        Some(mode) => format!("{} (amd_pstate {})", platform_profile, mode),
+                        None => platform_profile.clone(),
+                    }),
+                ]));
+            }
+
+            if let Some(in_sync) = state.limits_in_sync {
+                let reverts = match state.last_revert_at {
+                    Some(at) => format!(
+                        ", {} reverts, last {} ago",
+                        state.revert_count,
+                        format_duration(unix_now().saturating_sub(at))
+                    ),
+                    None => String::new(),
+                };
+                rows.push(
+                    Row::new(vec![
+                        Cell::from("Limits"),
+                        Cell::from(format!(
+                            "{}{}",
+                            if in_sync { "in sync" } else { "OUT OF SYNC" },
+                            reverts
+                        )),
+                    ])
+                    .style(if in_sync {
+                        Style::default()
+                    } else {
+                        Style::default().fg(Color::Red)
+                    }),
+                );
+            }
+
+            if let Some(error) = &state.hardware_error {
+                rows.push(
+                    Row::new(vec![Cell::from("Error"), Cell::from(error.clone())])
+                        .style(Style::default().fg(Color::Red)),
+                );
+            }
+
+            let stats = &state.daemon_stats;
+            rows.push(Row::new(vec![
+                Cell::from("Daemon"),
+                Cell::from(format!(
+                    "{} applies, {} skipped, {} spawns, {:.1} wakeups/s",
+                    stats.limit_applies,
+                    stats.skipped_applies,
+                    stats.processes_spawned,
+                    stats.wakeups_per_sec
+                )),
+            ]));
+
+            // A dry-run daemon gets a third column listing what it would do
+            let bottom_constraints = if state.dry_run {
+                vec![
+                    Constraint::Percentage(35),
+                    Constraint::Percentage(25),
+                    Constraint::Percentage(40),
+                ]
+            } else {
+                vec![Constraint::Percentage(50), Constraint::Percentage(50)]
+            };
+            let bottom = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints(bottom_constraints)
+                .split(chunks[2]);
 
             let table = Table::new(
                 rows,
                 [Constraint::Percentage(50), Constraint::Percentage(50)],
             )
             .block(Block::default().title("Details").borders(Borders::ALL));
-            f.render_widget(table, chunks[2]);
+            f.render_widget(table, bottom[0]);
+
+            // 4. Temperatures
+            let temp_rows = state.temperatures.iter().map(|t| {
+                let color = if t.celsius >= 85.0 {
+                    Color::Red
+                } else if t.celsius >= 70.0 {
+                    Color::Yellow
+                } else {
+                    Color::Green
+                };
+                Row::new(vec![
+                    Cell::from(t.label.clone()),
+                    Cell::from(format!("{:.1} °C", t.celsius)).style(Style::default().fg(color)),
+                ])
+            });
+
+            let temp_table = Table::new(
+                temp_rows,
+                [Constraint::Percentage(60), Constraint::Percentage(40)],
+            )
+            .block(Block::default().title("Temperatures").borders(Borders::ALL));
+            f.render_widget(temp_table, bottom[1]);
+
+            // 5. Would Apply (dry run only), most recent first
+            if state.dry_run {
+                let planned_rows = planned.iter().rev().map(|p| {
+                    Row::new(vec![
+                        Cell::from(format!("{}x", p.count)),
+                        Cell::from(p.action.clone()),
+                    ])
+                });
+                let planned_table = Table::new(
+                    planned_rows,
+                    [Constraint::Length(6), Constraint::Percentage(100)],
+                )
+                .block(
+                    Block::default()
+                        .title("Would Apply (dry run)")
+                        .borders(Borders::ALL),
+                );
+                f.render_widget(planned_table, bottom[2]);
+            }
         })?;
 
         let timeout = tick_rate
@@ -206,6 +470,15 @@ async fn main() -> Result<()> {
                     KeyCode::Char('p') => {
                         let _ = tx_cmd.send(DaemonCommand::SetProfile(Profile::Pro)).await;
                     }
+                    KeyCode::Char('w') => {
+                        let _ = tx_cmd.send(DaemonCommand::ToggleWifi).await;
+                    }
+                    KeyCode::Char('b') => {
+                        let _ = tx_cmd.send(DaemonCommand::ToggleBluetooth).await;
+                    }
+                    KeyCode::Char('c') => {
+                        let _ = tx_cmd.send(DaemonCommand::ResumeContainers).await;
+                    }
                     _ => {}
                 }
             }
@@ -214,6 +487,19 @@ async fn main() -> Result<()> {
         // Check for new state
         while let Ok(new_state) = rx_state.try_recv() {
             state = new_state;
+            // The daemon refreshes attribution once per tick, so ask along with each state
+            let _ = tx_cmd.try_send(DaemonCommand::GetTopConsumers(TOP_CONSUMERS));
+            if state.dry_run {
+                let _ = tx_cmd.try_send(DaemonCommand::GetPlannedActions);
+            }
+        }
+
+        while let Ok(resp) = rx_resp.try_recv() {
+            match resp {
+                DaemonResponse::TopConsumers(list) => top_consumers = list,
+                DaemonResponse::RyzenInfo(_) => {}
+                DaemonResponse::PlannedActions(list) => planned = list,
+            }
         }
 
         if last_tick.elapsed() >= tick_rate {
@@ -228,3 +514,24 @@ async fn main() -> Result<()> {
 
     Ok(())
 }
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+fn format_duration(secs: u64) -> String {
+    let minutes = secs / 60;
+    format!("{}h {:02}m", minutes / 60, minutes % 60)
+}
+
+fn radio_label(radio: &RadioState) -> &'static str {
+    match radio {
+        RadioState::On => "ON",
+        RadioState::SoftBlocked => "OFF",
+        RadioState::HardBlocked => "OFF (hardware switch)",
+        RadioState::Absent => "N/A",
+    }
+}